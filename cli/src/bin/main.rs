@@ -25,7 +25,10 @@ fn from_rpc_block(block: &RPCBlock) -> HeaderParams {
         timestamp: U256::from_str(&block.timestamp).unwrap().as_u64(),
         number: U256::from_str(block.number.as_ref().unwrap()).unwrap(),
         difficulty: U256::from_str(&block.difficulty).unwrap(),
+        prev_randao: H256::default(),
         gas_limit: Gas::from_str(&block.gas_limit).unwrap(),
+        base_fee: Gas::zero(),
+        excess_blob_gas: Gas::zero(),
     }
 }
 
@@ -198,7 +201,10 @@ fn main() {
             timestamp: 0,
             number: U256::from_str(block_number).unwrap(),
             difficulty: U256::zero(),
+            prev_randao: H256::default(),
             gas_limit: Gas::zero(),
+            base_fee: Gas::zero(),
+            excess_blob_gas: Gas::zero(),
         }
     };
 
@@ -230,6 +236,7 @@ fn main() {
             apprent_value: value,
             is_system: false,
             is_static: false,
+            blob_versioned_hashes: Rc::new(Vec::new()),
         };
 
         match matches.value_of("PATCH") {
@@ -247,6 +254,8 @@ fn main() {
             value,
             gas_limit,
             gas_price,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
             input: Rc::new(data),
             nonce: match client {
                 Some(ref mut client) => {
@@ -259,14 +268,17 @@ fn main() {
             } else {
                 TransactionAction::Call(address)
             },
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Rc::new(Vec::new()),
         };
 
         match matches.value_of("PATCH") {
-            Some("frontier") => Box::new(SeqTransactionVM::new(&frontier, transaction, block)),
-            Some("homestead") => Box::new(SeqTransactionVM::new(&homestead, transaction, block)),
-            Some("eip150") => Box::new(SeqTransactionVM::new(&eip150, transaction, block)),
-            Some("eip160") => Box::new(SeqTransactionVM::new(&eip160, transaction, block)),
-            Some("byzantium") => Box::new(SeqTransactionVM::new(&byzantium, transaction, block)),
+            Some("frontier") => Box::new(SeqTransactionVM::new(&frontier, transaction, block).unwrap()),
+            Some("homestead") => Box::new(SeqTransactionVM::new(&homestead, transaction, block).unwrap()),
+            Some("eip150") => Box::new(SeqTransactionVM::new(&eip150, transaction, block).unwrap()),
+            Some("eip160") => Box::new(SeqTransactionVM::new(&eip160, transaction, block).unwrap()),
+            Some("byzantium") => Box::new(SeqTransactionVM::new(&byzantium, transaction, block).unwrap()),
             _ => panic!("Unsupported patch."),
         }
     };
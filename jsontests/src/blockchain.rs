@@ -22,6 +22,7 @@ pub struct JSONBlock {
     timestamp: u64,
     number: U256,
     difficulty: U256,
+    prev_randao: H256,
     gas_limit: Gas,
 
     logs: Vec<Log>,
@@ -36,7 +37,10 @@ impl JSONBlock {
             timestamp: self.timestamp,
             number: self.number,
             difficulty: self.difficulty,
+            prev_randao: self.prev_randao,
             gas_limit: self.gas_limit,
+            base_fee: Gas::zero(),
+            excess_blob_gas: Gas::zero(),
         }
     }
 
@@ -213,6 +217,7 @@ pub fn create_block(v: &Value) -> JSONBlock {
 
         let current_coinbase = env["currentCoinbase"].as_str().unwrap();
         let current_difficulty = env["currentDifficulty"].as_str().unwrap();
+        let current_random = env["currentRandom"].as_str();
         let current_gas_limit = env["currentGasLimit"].as_str().unwrap();
         let current_number = env["currentNumber"].as_str().unwrap();
         let current_timestamp = env["currentTimestamp"].as_str().unwrap();
@@ -225,6 +230,9 @@ pub fn create_block(v: &Value) -> JSONBlock {
 
             beneficiary: Address::from_str(current_coinbase).unwrap(),
             difficulty: read_u256(current_difficulty),
+            prev_randao: current_random
+                .map(|random| H256::from(read_u256(random)))
+                .unwrap_or_default(),
             gas_limit: Gas::from(read_u256(current_gas_limit)),
             number: read_u256(current_number),
             timestamp: read_u256(current_timestamp).into(),
@@ -276,5 +284,6 @@ pub fn create_context(v: &Value) -> Context {
         apprent_value: value,
         is_system: false,
         is_static: false,
+        blob_versioned_hashes: Rc::new(Vec::new()),
     }
 }
@@ -18,19 +18,26 @@
 //!
 //! ```
 //! use evm::{SeqTransactionVM, ValidTransaction, TransactionAction, HeaderParams};
-//! use evm_network::{DynamicPatch, DynamicAccountPatch, PRECOMPILEDS};
-//! use bigint::{Gas, U256, Address};
+//! use evm_network::{DynamicPatch, DynamicAccountPatch, NativeCryptoProvider, PrecompiledRegistry, PRECOMPILEDS};
+//! use bigint::{Gas, U256, H256, Address};
+//! use std::num::{NonZeroU64, NonZeroUsize};
 //! use std::rc::Rc;
+//! use std::sync::Arc;
 //!
 //! fn main() {
 //!   let transaction = ValidTransaction {
 //!      caller: Some(Address::default()),
 //!      gas_price: Gas::zero(),
+//!      max_fee_per_gas: None,
+//!      max_priority_fee_per_gas: None,
 //!      gas_limit: Gas::max_value(),
 //!      action: TransactionAction::Create,
 //!      value: U256::zero(),
 //!      input: Rc::new(Vec::new()),
-//!      nonce: U256::zero()
+//!      nonce: U256::zero(),
+//!      access_list: Vec::new(),
+//!      max_fee_per_blob_gas: None,
+//!      blob_versioned_hashes: Rc::new(Vec::new())
 //!   };
 //!
 //!   // Block Header
@@ -39,7 +46,10 @@
 //!      timestamp: 0,
 //!      number: U256::zero(),
 //!      difficulty: U256::zero(),
-//!      gas_limit: Gas::zero()
+//!      prev_randao: H256::default(),
+//!      gas_limit: Gas::zero(),
+//!      base_fee: Gas::zero(),
+//!      excess_blob_gas: Gas::zero(),
 //!   };
 //!
 //!   // Account Patch for ETC MainNet
@@ -50,6 +60,10 @@
 //!      allow_partial_change: true
 //!   };
 //!
+//!   // Register all eight precompiled contracts, enabled from genesis.
+//!   // Clients may insert their own or remove some per address.
+//!   let precompileds = Arc::new(PrecompiledRegistry::from_static(&PRECOMPILEDS, U256::zero()));
+//!
 //!   // Patch for Constantinople hardfork
 //!   let patch = DynamicPatch {
 //!      account_patch,
@@ -57,6 +71,7 @@
 //!      callstack_limit: 1024,
 //!      gas_extcode: Gas::from(700_usize),
 //!      gas_balance: Gas::from(400_usize),
+//!      gas_extcodehash: Gas::from(400_usize),
 //!      gas_sload: Gas::from(200_usize),
 //!      gas_suicide: Gas::from(5000_usize),
 //!      gas_suicide_new_account: Gas::from(25000_usize),
@@ -72,19 +87,43 @@
 //!      has_extcodehash: true,
 //!      has_create2: true,
 //!      has_reduced_sstore_gas_metering: true,
+//!      has_net_sstore_gas_metering: false,
+//!      has_warm_cold_access: false,
+//!      gas_warm_access: Gas::from(100_usize),
+//!      gas_cold_account_access: Gas::from(2600_usize),
+//!      gas_cold_sload: Gas::from(2100_usize),
+//!      has_chain_id: false,
+//!      chain_id: U256::zero(),
+//!      has_self_balance: false,
+//!      has_base_fee: false,
+//!      max_refund_quotient: NonZeroUsize::new(2).unwrap(),
+//!      refund_sstore_clears: Gas::from(15000_usize),
+//!      refund_suicide: Gas::from(24000_usize),
+//!      has_push0: false,
+//!      initcode_limit: None,
+//!      gas_initcode_word: Gas::zero(),
+//!      has_transient_storage: false,
+//!      has_mcopy: false,
+//!      has_restricted_suicide: false,
+//!      has_ef_code_rejection: false,
+//!      has_blobs: false,
+//!      blob_base_fee_update_fraction: NonZeroU64::new(3338477).unwrap(),
+//!      has_prev_randao: false,
+//!      has_eof: false,
 //!      err_on_call_with_more_gas: false,
 //!      call_create_l64_after_gas: true,
 //!      memory_limit: usize::max_value(),
-//!      // Enable all eight precompiled contracts by their addresses
-//!      enabled_precompileds: (0x1..=0x8).into_iter().map(Address::from).collect(),
-//!      precompileds: &PRECOMPILEDS
+//!      // Enable the precompiled contracts activated at this block
+//!      enabled_precompileds: precompileds.enabled_at(header.number),
+//!      precompileds,
+//!      crypto: Arc::new(NativeCryptoProvider)
 //!   };
 //!
 //!   SeqTransactionVM::new(
 //!       &patch,
 //!       transaction,
 //!       header
-//!   );
+//!   ).unwrap();
 //! }
 //! ```
 //!
@@ -98,7 +137,7 @@
 //! ```
 //! use evm::{SeqTransactionVM, ValidTransaction, TransactionAction, HeaderParams, Precompiled};
 //! use evm_network::{AccountPatch, Patch, PRECOMPILEDS};
-//! use bigint::{Gas, U256, Address};
+//! use bigint::{Gas, U256, H256, Address};
 //! use std::rc::Rc;
 //!
 //! struct MainnetAccountPatch;
@@ -152,11 +191,16 @@
 //!   let transaction = ValidTransaction {
 //!      caller: Some(Address::default()),
 //!      gas_price: Gas::zero(),
+//!      max_fee_per_gas: None,
+//!      max_priority_fee_per_gas: None,
 //!      gas_limit: Gas::max_value(),
 //!      action: TransactionAction::Create,
 //!      value: U256::zero(),
 //!      input: Rc::new(Vec::new()),
-//!      nonce: U256::zero()
+//!      nonce: U256::zero(),
+//!      access_list: Vec::new(),
+//!      max_fee_per_blob_gas: None,
+//!      blob_versioned_hashes: Rc::new(Vec::new())
 //!   };
 //!
 //!   // Block Header
@@ -165,7 +209,10 @@
 //!      timestamp: 0,
 //!      number: U256::zero(),
 //!      difficulty: U256::zero(),
-//!      gas_limit: Gas::zero()
+//!      prev_randao: H256::default(),
+//!      gas_limit: Gas::zero(),
+//!      base_fee: Gas::zero(),
+//!      excess_blob_gas: Gas::zero(),
 //!   };
 //!
 //!   let patch = ConstantinoplePatch;
@@ -174,7 +221,7 @@
 //!       &patch,
 //!       transaction,
 //!       header
-//!   );
+//!   ).unwrap();
 //! }
 //! ```
 
@@ -184,7 +231,7 @@ use evm_precompiled_bn128::{BN128_ADD_PRECOMPILED, BN128_MUL_PRECOMPILED, BN128_
 use evm_precompiled_modexp::MODEXP_PRECOMPILED;
 
 // Re-export DynamicPatch and Patch APIs
-pub use evm::{AccountPatch, DynamicAccountPatch, DynamicPatch, Patch};
+pub use evm::{AccountPatch, DynamicAccountPatch, DynamicPatch, NativeCryptoProvider, Patch, PrecompiledRegistry};
 
 #[rustfmt::skip]
 pub static PRECOMPILEDS: [(Address, Option<&'static [u8]>, &'static Precompiled); 8] = [
@@ -0,0 +1,170 @@
+use bigint::Gas;
+use std::rc::Rc;
+
+use evm::errors::{OnChainError, RuntimeError};
+use evm::Precompiled;
+
+pub static BLAKE2F_PRECOMPILED: Blake2FPrecompiled = Blake2FPrecompiled;
+
+/// Gas paid for every round of the compression function.
+const G_BLAKE2F_ROUND: u64 = 1;
+/// Length of the input: rounds, state vector, message block, offset
+/// counters and final block flag.
+const INPUT_LENGTH: usize = 4 + 64 + 128 + 16 + 1;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+#[allow(clippy::many_single_char_names)]
+fn g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// The BLAKE2b compression function F with a configurable number of
+/// rounds. (RFC7693, EIP152)
+fn compress(h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], f: bool, rounds: u32) {
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if f {
+        v[14] = !v[14];
+    }
+
+    for i in 0..rounds as usize {
+        let s = &SIGMA[i % 10];
+        g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+fn read_u64_le(data: &[u8]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+pub struct Blake2FPrecompiled;
+impl Precompiled for Blake2FPrecompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        if data.len() != INPUT_LENGTH {
+            return Err(RuntimeError::OnChain(OnChainError::PrecompiledFailure));
+        }
+
+        let rounds = u32::from(data[0]) << 24 | u32::from(data[1]) << 16 | u32::from(data[2]) << 8 | u32::from(data[3]);
+        let gas = Gas::from(u64::from(rounds) * G_BLAKE2F_ROUND);
+        if gas > gas_limit {
+            return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
+        }
+
+        let f = match data[212] {
+            0 => false,
+            1 => true,
+            _ => return Err(RuntimeError::OnChain(OnChainError::PrecompiledFailure)),
+        };
+
+        let mut h = [0u64; 8];
+        for (i, item) in h.iter_mut().enumerate() {
+            *item = read_u64_le(&data[(4 + i * 8)..]);
+        }
+        let mut m = [0u64; 16];
+        for (i, item) in m.iter_mut().enumerate() {
+            *item = read_u64_le(&data[(68 + i * 8)..]);
+        }
+        let t = [read_u64_le(&data[196..]), read_u64_le(&data[204..])];
+
+        compress(&mut h, &m, t, f, rounds);
+
+        let mut result = Vec::with_capacity(64);
+        for item in &h {
+            result.extend_from_slice(&item.to_le_bytes());
+        }
+
+        Ok((gas, Rc::new(result)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use hexutil::*;
+
+    /// Input compressing the single block "abc" with the BLAKE2b-512
+    /// initial state, as in the test vectors of EIP-152.
+    fn input(rounds: u32, flag: u8) -> Vec<u8> {
+        let mut data = rounds.to_be_bytes().to_vec();
+        for (i, iv) in IV.iter().enumerate() {
+            let h = if i == 0 { iv ^ 0x0101_0040 } else { *iv };
+            data.extend_from_slice(&h.to_le_bytes());
+        }
+        let mut m = [0u8; 128];
+        m[..3].copy_from_slice(b"abc");
+        data.extend_from_slice(&m);
+        data.extend_from_slice(&3u64.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(flag);
+        data
+    }
+
+    #[test]
+    fn spec_test_twelve_rounds() {
+        let (gas, output) = BLAKE2F_PRECOMPILED
+            .gas_and_step(&input(12, 1), Gas::from(100usize))
+            .unwrap();
+        let expected = read_hex("ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap();
+        assert_eq!(gas, Gas::from(12usize));
+        assert_eq!(expected, Rc::try_unwrap(output).unwrap());
+    }
+
+    #[test]
+    fn spec_test_invalid_input() {
+        let mut short = input(12, 1);
+        short.pop();
+        assert!(BLAKE2F_PRECOMPILED.gas_and_step(&short, Gas::from(100usize)).is_err());
+        assert!(BLAKE2F_PRECOMPILED
+            .gas_and_step(&input(12, 2), Gas::from(100usize))
+            .is_err());
+        assert!(BLAKE2F_PRECOMPILED
+            .gas_and_step(&input(12, 1), Gas::from(11usize))
+            .is_err());
+    }
+}
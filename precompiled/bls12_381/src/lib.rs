@@ -0,0 +1,414 @@
+use bigint::Gas;
+use std::rc::Rc;
+
+use ark_bls12_381::{g1, g2, Bls12_381, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::hashing::curve_maps::wb::WBMap;
+use ark_ec::hashing::map_to_curve_hasher::MapToCurve;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{BigInt, One, PrimeField};
+
+use evm::errors::{OnChainError, RuntimeError};
+use evm::Precompiled;
+
+pub static BLS12_381_G1_ADD_PRECOMPILED: Bls12381G1AddPrecompiled = Bls12381G1AddPrecompiled;
+pub static BLS12_381_G1_MSM_PRECOMPILED: Bls12381G1MsmPrecompiled = Bls12381G1MsmPrecompiled;
+pub static BLS12_381_G2_ADD_PRECOMPILED: Bls12381G2AddPrecompiled = Bls12381G2AddPrecompiled;
+pub static BLS12_381_G2_MSM_PRECOMPILED: Bls12381G2MsmPrecompiled = Bls12381G2MsmPrecompiled;
+pub static BLS12_381_PAIRING_PRECOMPILED: Bls12381PairingPrecompiled = Bls12381PairingPrecompiled;
+pub static BLS12_381_MAP_FP_TO_G1_PRECOMPILED: Bls12381MapFpToG1Precompiled = Bls12381MapFpToG1Precompiled;
+pub static BLS12_381_MAP_FP2_TO_G2_PRECOMPILED: Bls12381MapFp2ToG2Precompiled = Bls12381MapFp2ToG2Precompiled;
+
+const G_G1ADD: usize = 375;
+const G_G2ADD: usize = 600;
+const G_G1MUL: usize = 12000;
+const G_G2MUL: usize = 22500;
+const G_PAIRING_BASE: usize = 37700;
+const G_PAIRING_PER_PAIR: usize = 32600;
+const G_MAP_FP_TO_G1: usize = 5500;
+const G_MAP_FP2_TO_G2: usize = 23800;
+
+/// Field elements are padded to 64 bytes, with the top 16 bytes zero.
+const FP_LENGTH: usize = 64;
+const FP2_LENGTH: usize = 2 * FP_LENGTH;
+const G1_LENGTH: usize = 2 * FP_LENGTH;
+const G2_LENGTH: usize = 2 * FP2_LENGTH;
+const SCALAR_LENGTH: usize = 32;
+
+/// Discounts in thousandths for a multi-scalar multiplication of `k`
+/// pairs, indexed by `k - 1`. Larger `k` use the last entry. (EIP2537)
+const G1_MSM_DISCOUNT: [u16; 128] = [
+    1000, 949, 848, 797, 764, 750, 738, 728, 719, 712, 705, 698, 692, 687, 682, 677, 673, 669, 665, 661, 658, 654, 651,
+    648, 645, 642, 640, 637, 635, 632, 630, 627, 625, 623, 621, 619, 617, 615, 613, 611, 609, 608, 606, 604, 603, 601,
+    599, 598, 596, 595, 593, 592, 591, 589, 588, 586, 585, 584, 582, 581, 580, 579, 577, 576, 575, 574, 573, 572, 570,
+    569, 568, 567, 566, 565, 564, 563, 562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 551, 550, 549, 548, 547,
+    547, 546, 545, 544, 543, 542, 541, 540, 540, 539, 538, 537, 536, 536, 535, 534, 533, 532, 532, 531, 530, 529, 528,
+    528, 527, 526, 525, 525, 524, 523, 522, 522, 521, 520, 520, 519,
+];
+const G2_MSM_DISCOUNT: [u16; 128] = [
+    1000, 1000, 923, 884, 855, 832, 812, 796, 782, 770, 759, 749, 740, 732, 724, 717, 711, 704, 699, 693, 688, 683,
+    679, 674, 670, 666, 663, 659, 655, 652, 649, 646, 643, 640, 637, 634, 632, 629, 627, 624, 622, 620, 618, 615, 613,
+    611, 609, 607, 606, 604, 602, 600, 598, 597, 595, 593, 592, 590, 589, 587, 586, 584, 583, 582, 580, 579, 578, 576,
+    575, 574, 573, 571, 570, 569, 568, 567, 566, 565, 563, 562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 552,
+    551, 550, 549, 548, 547, 546, 545, 545, 544, 543, 542, 541, 541, 540, 539, 538, 537, 537, 536, 535, 535, 534, 533,
+    532, 532, 531, 530, 530, 529, 528, 528, 527, 526, 526, 525, 524, 524,
+];
+
+fn invalid_input() -> RuntimeError {
+    RuntimeError::OnChain(OnChainError::PrecompiledFailure)
+}
+
+fn check_gas(gas: Gas, gas_limit: Gas) -> Result<(), RuntimeError> {
+    if gas > gas_limit {
+        Err(RuntimeError::OnChain(OnChainError::EmptyGas))
+    } else {
+        Ok(())
+    }
+}
+
+fn msm_gas(k: usize, discounts: &[u16; 128], mul_gas: usize) -> Gas {
+    let discount = discounts[k.min(discounts.len()) - 1];
+    Gas::from(k) * Gas::from(mul_gas) * Gas::from(discount as usize) / Gas::from(1000usize)
+}
+
+/// Read a base field element, rejecting non-zero padding and values not
+/// less than the modulus.
+fn read_fq(data: &[u8]) -> Result<Fq, RuntimeError> {
+    if data[..16].iter().any(|b| *b != 0) {
+        return Err(invalid_input());
+    }
+
+    let mut limbs = [0u64; 6];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let start = FP_LENGTH - (i + 1) * 8;
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&data[start..(start + 8)]);
+        *limb = u64::from_be_bytes(bytes);
+    }
+    Fq::from_bigint(BigInt(limbs)).ok_or_else(invalid_input)
+}
+
+fn read_fq2(data: &[u8]) -> Result<Fq2, RuntimeError> {
+    Ok(Fq2::new(
+        read_fq(&data[..FP_LENGTH])?,
+        read_fq(&data[FP_LENGTH..FP2_LENGTH])?,
+    ))
+}
+
+fn write_fq(out: &mut Vec<u8>, value: &Fq) {
+    out.extend_from_slice(&[0u8; 16]);
+    for limb in value.into_bigint().0.iter().rev() {
+        out.extend_from_slice(&limb.to_be_bytes());
+    }
+}
+
+fn write_fq2(out: &mut Vec<u8>, value: &Fq2) {
+    write_fq(out, &value.c0);
+    write_fq(out, &value.c1);
+}
+
+/// Read a G1 point, where all zeros encode the point at infinity. The
+/// subgroup check is only required for multiplications and pairings.
+fn read_g1(data: &[u8], subgroup_check: bool) -> Result<G1Affine, RuntimeError> {
+    let x = read_fq(&data[..FP_LENGTH])?;
+    let y = read_fq(&data[FP_LENGTH..G1_LENGTH])?;
+    if x == Fq::default() && y == Fq::default() {
+        return Ok(G1Affine::identity());
+    }
+
+    let point = G1Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || (subgroup_check && !point.is_in_correct_subgroup_assuming_on_curve()) {
+        return Err(invalid_input());
+    }
+    Ok(point)
+}
+
+fn read_g2(data: &[u8], subgroup_check: bool) -> Result<G2Affine, RuntimeError> {
+    let x = read_fq2(&data[..FP2_LENGTH])?;
+    let y = read_fq2(&data[FP2_LENGTH..G2_LENGTH])?;
+    if x == Fq2::default() && y == Fq2::default() {
+        return Ok(G2Affine::identity());
+    }
+
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || (subgroup_check && !point.is_in_correct_subgroup_assuming_on_curve()) {
+        return Err(invalid_input());
+    }
+    Ok(point)
+}
+
+fn write_g1(point: &G1Affine) -> Vec<u8> {
+    let mut out = Vec::with_capacity(G1_LENGTH);
+    match point.xy() {
+        Some((x, y)) => {
+            write_fq(&mut out, &x);
+            write_fq(&mut out, &y);
+        }
+        None => out.resize(G1_LENGTH, 0),
+    }
+    out
+}
+
+fn write_g2(point: &G2Affine) -> Vec<u8> {
+    let mut out = Vec::with_capacity(G2_LENGTH);
+    match point.xy() {
+        Some((x, y)) => {
+            write_fq2(&mut out, &x);
+            write_fq2(&mut out, &y);
+        }
+        None => out.resize(G2_LENGTH, 0),
+    }
+    out
+}
+
+/// Scalars are 32-byte big-endian integers that are not required to be
+/// reduced. Reducing them is fine as the points are in the subgroup.
+fn read_scalar(data: &[u8]) -> Fr {
+    Fr::from_be_bytes_mod_order(&data[..SCALAR_LENGTH])
+}
+
+pub struct Bls12381G1AddPrecompiled;
+impl Precompiled for Bls12381G1AddPrecompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let gas = Gas::from(G_G1ADD);
+        check_gas(gas, gas_limit)?;
+        if data.len() != 2 * G1_LENGTH {
+            return Err(invalid_input());
+        }
+
+        let p1 = read_g1(&data[..G1_LENGTH], false)?;
+        let p2 = read_g1(&data[G1_LENGTH..], false)?;
+        let sum = (p1.into_group() + p2).into_affine();
+
+        Ok((gas, Rc::new(write_g1(&sum))))
+    }
+}
+
+pub struct Bls12381G1MsmPrecompiled;
+impl Precompiled for Bls12381G1MsmPrecompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let pair_length = G1_LENGTH + SCALAR_LENGTH;
+        if data.is_empty() || !data.len().is_multiple_of(pair_length) {
+            return Err(invalid_input());
+        }
+        let k = data.len() / pair_length;
+        let gas = msm_gas(k, &G1_MSM_DISCOUNT, G_G1MUL);
+        check_gas(gas, gas_limit)?;
+
+        let mut bases = Vec::with_capacity(k);
+        let mut scalars = Vec::with_capacity(k);
+        for pair in data.chunks(pair_length) {
+            bases.push(read_g1(&pair[..G1_LENGTH], true)?);
+            scalars.push(read_scalar(&pair[G1_LENGTH..]));
+        }
+        let result = G1Projective::msm(&bases, &scalars).map_err(|_| invalid_input())?;
+
+        Ok((gas, Rc::new(write_g1(&result.into_affine()))))
+    }
+}
+
+pub struct Bls12381G2AddPrecompiled;
+impl Precompiled for Bls12381G2AddPrecompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let gas = Gas::from(G_G2ADD);
+        check_gas(gas, gas_limit)?;
+        if data.len() != 2 * G2_LENGTH {
+            return Err(invalid_input());
+        }
+
+        let p1 = read_g2(&data[..G2_LENGTH], false)?;
+        let p2 = read_g2(&data[G2_LENGTH..], false)?;
+        let sum = (p1.into_group() + p2).into_affine();
+
+        Ok((gas, Rc::new(write_g2(&sum))))
+    }
+}
+
+pub struct Bls12381G2MsmPrecompiled;
+impl Precompiled for Bls12381G2MsmPrecompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let pair_length = G2_LENGTH + SCALAR_LENGTH;
+        if data.is_empty() || !data.len().is_multiple_of(pair_length) {
+            return Err(invalid_input());
+        }
+        let k = data.len() / pair_length;
+        let gas = msm_gas(k, &G2_MSM_DISCOUNT, G_G2MUL);
+        check_gas(gas, gas_limit)?;
+
+        let mut bases = Vec::with_capacity(k);
+        let mut scalars = Vec::with_capacity(k);
+        for pair in data.chunks(pair_length) {
+            bases.push(read_g2(&pair[..G2_LENGTH], true)?);
+            scalars.push(read_scalar(&pair[G2_LENGTH..]));
+        }
+        let result = G2Projective::msm(&bases, &scalars).map_err(|_| invalid_input())?;
+
+        Ok((gas, Rc::new(write_g2(&result.into_affine()))))
+    }
+}
+
+pub struct Bls12381PairingPrecompiled;
+impl Precompiled for Bls12381PairingPrecompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let pair_length = G1_LENGTH + G2_LENGTH;
+        if data.is_empty() || !data.len().is_multiple_of(pair_length) {
+            return Err(invalid_input());
+        }
+        let k = data.len() / pair_length;
+        let gas = Gas::from(G_PAIRING_PER_PAIR) * Gas::from(k) + Gas::from(G_PAIRING_BASE);
+        check_gas(gas, gas_limit)?;
+
+        let mut g1s = Vec::with_capacity(k);
+        let mut g2s = Vec::with_capacity(k);
+        for pair in data.chunks(pair_length) {
+            let g1 = read_g1(&pair[..G1_LENGTH], true)?;
+            let g2 = read_g2(&pair[G1_LENGTH..], true)?;
+            if !g1.is_zero() && !g2.is_zero() {
+                g1s.push(g1);
+                g2s.push(g2);
+            }
+        }
+
+        let mut result = vec![0u8; 32];
+        if Bls12_381::multi_pairing(g1s, g2s).0.is_one() {
+            result[31] = 1;
+        }
+
+        Ok((gas, Rc::new(result)))
+    }
+}
+
+pub struct Bls12381MapFpToG1Precompiled;
+impl Precompiled for Bls12381MapFpToG1Precompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let gas = Gas::from(G_MAP_FP_TO_G1);
+        check_gas(gas, gas_limit)?;
+        if data.len() != FP_LENGTH {
+            return Err(invalid_input());
+        }
+
+        let element = read_fq(data)?;
+        let point = WBMap::<g1::Config>::map_to_curve(element)
+            .map_err(|_| invalid_input())?
+            .clear_cofactor();
+
+        Ok((gas, Rc::new(write_g1(&point))))
+    }
+}
+
+pub struct Bls12381MapFp2ToG2Precompiled;
+impl Precompiled for Bls12381MapFp2ToG2Precompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let gas = Gas::from(G_MAP_FP2_TO_G2);
+        check_gas(gas, gas_limit)?;
+        if data.len() != FP2_LENGTH {
+            return Err(invalid_input());
+        }
+
+        let element = read_fq2(data)?;
+        let point = WBMap::<g2::Config>::map_to_curve(element)
+            .map_err(|_| invalid_input())?
+            .clear_cofactor();
+
+        Ok((gas, Rc::new(write_g2(&point))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::ops::Neg;
+
+    fn generators() -> (Vec<u8>, Vec<u8>) {
+        (write_g1(&G1Affine::generator()), write_g2(&G2Affine::generator()))
+    }
+
+    fn scalar(value: u8) -> Vec<u8> {
+        let mut scalar = vec![0u8; SCALAR_LENGTH];
+        scalar[31] = value;
+        scalar
+    }
+
+    #[test]
+    fn encoding_round_trip() {
+        let (g1, g2) = generators();
+        assert_eq!(read_g1(&g1, true).unwrap(), G1Affine::generator());
+        assert_eq!(read_g2(&g2, true).unwrap(), G2Affine::generator());
+        assert_eq!(read_g1(&[0u8; G1_LENGTH], true).unwrap(), G1Affine::identity());
+
+        let mut padded = g1.clone();
+        padded[0] = 1;
+        assert!(read_g1(&padded, false).is_err());
+        let mut off_curve = g1;
+        off_curve[G1_LENGTH - 1] ^= 1;
+        assert!(read_g1(&off_curve, false).is_err());
+    }
+
+    #[test]
+    fn add_and_msm() {
+        let (g1, g2) = generators();
+        let limit = Gas::from(1000000usize);
+
+        let (gas, sum) = BLS12_381_G1_ADD_PRECOMPILED
+            .gas_and_step(&[g1.clone(), g1.clone()].concat(), limit)
+            .unwrap();
+        assert_eq!(gas, Gas::from(375usize));
+        let (gas, doubled) = BLS12_381_G1_MSM_PRECOMPILED
+            .gas_and_step(&[g1.clone(), scalar(2)].concat(), limit)
+            .unwrap();
+        assert_eq!(gas, Gas::from(12000usize));
+        assert_eq!(sum, doubled);
+
+        let (_, sum) = BLS12_381_G2_ADD_PRECOMPILED
+            .gas_and_step(&[g2.clone(), g2.clone()].concat(), limit)
+            .unwrap();
+        let (gas, doubled) = BLS12_381_G2_MSM_PRECOMPILED
+            .gas_and_step(&[g2.clone(), scalar(1), g2, scalar(1)].concat(), limit)
+            .unwrap();
+        assert_eq!(gas, Gas::from(45000usize));
+        assert_eq!(sum, doubled);
+
+        assert!(BLS12_381_G1_MSM_PRECOMPILED.gas_and_step(&[], limit).is_err());
+        assert!(BLS12_381_G1_MSM_PRECOMPILED
+            .gas_and_step(&[g1, scalar(2)].concat(), Gas::from(11999usize))
+            .is_err());
+    }
+
+    #[test]
+    fn pairing() {
+        let (g1, g2) = generators();
+        let neg_g1 = write_g1(&G1Affine::generator().neg());
+        let limit = Gas::from(1000000usize);
+
+        let (gas, output) = BLS12_381_PAIRING_PRECOMPILED
+            .gas_and_step(&[g1.clone(), g2.clone(), neg_g1, g2.clone()].concat(), limit)
+            .unwrap();
+        assert_eq!(gas, Gas::from(102900usize));
+        assert_eq!(output[31], 1);
+
+        let (_, output) = BLS12_381_PAIRING_PRECOMPILED
+            .gas_and_step(&[g1, g2].concat(), limit)
+            .unwrap();
+        assert_eq!(output[31], 0);
+    }
+
+    #[test]
+    fn map_to_curve() {
+        let limit = Gas::from(1000000usize);
+
+        let mut fp = vec![0u8; FP_LENGTH];
+        fp[FP_LENGTH - 1] = 1;
+        let (gas, output) = BLS12_381_MAP_FP_TO_G1_PRECOMPILED.gas_and_step(&fp, limit).unwrap();
+        assert_eq!(gas, Gas::from(5500usize));
+        assert!(read_g1(&output, true).is_ok());
+
+        let fp2 = [fp.clone(), fp].concat();
+        let (gas, output) = BLS12_381_MAP_FP2_TO_G2_PRECOMPILED.gas_and_step(&fp2, limit).unwrap();
+        assert_eq!(gas, Gas::from(23800usize));
+        assert!(read_g2(&output, true).is_ok());
+
+        assert!(BLS12_381_MAP_FP_TO_G1_PRECOMPILED
+            .gas_and_step(&[0xffu8; FP_LENGTH], limit)
+            .is_err());
+    }
+}
@@ -4,14 +4,27 @@ use std::rc::Rc;
 use evm::errors::{OnChainError, RuntimeError};
 use evm::Precompiled;
 
-pub static BN128_ADD_PRECOMPILED: Bn128AddPrecompiled = Bn128AddPrecompiled;
+pub static BN128_ADD_PRECOMPILED: Bn128AddPrecompiled = Bn128AddPrecompiled::BYZANTIUM;
+pub static ISTANBUL_BN128_ADD_PRECOMPILED: Bn128AddPrecompiled = Bn128AddPrecompiled::ISTANBUL;
+
+/// bn128 point addition, charging a constant gas cost.
+pub struct Bn128AddPrecompiled {
+    /// Gas cost of an addition.
+    pub gas: usize,
+}
+
+impl Bn128AddPrecompiled {
+    /// Byzantium gas cost.
+    pub const BYZANTIUM: Bn128AddPrecompiled = Bn128AddPrecompiled { gas: 500 };
+    /// Istanbul gas cost. (EIP1108)
+    pub const ISTANBUL: Bn128AddPrecompiled = Bn128AddPrecompiled { gas: 150 };
+}
 
-pub struct Bn128AddPrecompiled;
 impl Precompiled for Bn128AddPrecompiled {
     fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
         use bn::{AffineG1, Fq, Group, G1};
 
-        let gas = Gas::from(500usize);
+        let gas = Gas::from(self.gas);
         if gas > gas_limit {
             return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
         }
@@ -22,23 +35,23 @@ impl Precompiled for Bn128AddPrecompiled {
             data.push(0);
         }
 
-        let px = Fq::from_slice(&data[0..32]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-        let py = Fq::from_slice(&data[32..64]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-        let qx = Fq::from_slice(&data[64..96]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-        let qy = Fq::from_slice(&data[96..128]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
+        let px = Fq::from_slice(&data[0..32]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+        let py = Fq::from_slice(&data[32..64]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+        let qx = Fq::from_slice(&data[64..96]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+        let qy = Fq::from_slice(&data[96..128]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
 
         let p = if px == Fq::zero() && py == Fq::zero() {
             G1::zero()
         } else {
             AffineG1::new(px, py)
-                .map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?
+                .map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?
                 .into()
         };
         let q = if qx == Fq::zero() && qy == Fq::zero() {
             G1::zero()
         } else {
             AffineG1::new(qx, qy)
-                .map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?
+                .map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?
                 .into()
         };
 
@@ -52,14 +65,27 @@ impl Precompiled for Bn128AddPrecompiled {
     }
 }
 
-pub static BN128_MUL_PRECOMPILED: Bn128MulPrecompiled = Bn128MulPrecompiled;
+pub static BN128_MUL_PRECOMPILED: Bn128MulPrecompiled = Bn128MulPrecompiled::BYZANTIUM;
+pub static ISTANBUL_BN128_MUL_PRECOMPILED: Bn128MulPrecompiled = Bn128MulPrecompiled::ISTANBUL;
+
+/// bn128 scalar multiplication, charging a constant gas cost.
+pub struct Bn128MulPrecompiled {
+    /// Gas cost of a multiplication.
+    pub gas: usize,
+}
+
+impl Bn128MulPrecompiled {
+    /// Byzantium gas cost.
+    pub const BYZANTIUM: Bn128MulPrecompiled = Bn128MulPrecompiled { gas: 40000 };
+    /// Istanbul gas cost. (EIP1108)
+    pub const ISTANBUL: Bn128MulPrecompiled = Bn128MulPrecompiled { gas: 6000 };
+}
 
-pub struct Bn128MulPrecompiled;
 impl Precompiled for Bn128MulPrecompiled {
     fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
         use bn::{AffineG1, Fq, Fr, Group, G1};
 
-        let gas = Gas::from(40000usize);
+        let gas = Gas::from(self.gas);
         if gas > gas_limit {
             return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
         }
@@ -70,15 +96,15 @@ impl Precompiled for Bn128MulPrecompiled {
             data.push(0);
         }
 
-        let px = Fq::from_slice(&data[0..32]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-        let py = Fq::from_slice(&data[32..64]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-        let fr = Fr::from_slice(&data[64..96]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
+        let px = Fq::from_slice(&data[0..32]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+        let py = Fq::from_slice(&data[32..64]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+        let fr = Fr::from_slice(&data[64..96]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
 
         let p = if px == Fq::zero() && py == Fq::zero() {
             G1::zero()
         } else {
             AffineG1::new(px, py)
-                .map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?
+                .map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?
                 .into()
         };
 
@@ -92,20 +118,46 @@ impl Precompiled for Bn128MulPrecompiled {
     }
 }
 
-pub static BN128_PAIRING_PRECOMPILED: Bn128PairingPrecompiled = Bn128PairingPrecompiled;
+pub static BN128_PAIRING_PRECOMPILED: Bn128PairingPrecompiled = Bn128PairingPrecompiled::BYZANTIUM;
+pub static ISTANBUL_BN128_PAIRING_PRECOMPILED: Bn128PairingPrecompiled = Bn128PairingPrecompiled::ISTANBUL;
+
+/// bn128 pairing check, charging a base gas cost plus a cost for every
+/// pair of points.
+pub struct Bn128PairingPrecompiled {
+    /// Gas cost of a pairing check.
+    pub base_gas: usize,
+    /// Gas cost of every pair of points.
+    pub pair_gas: usize,
+}
+
+impl Bn128PairingPrecompiled {
+    /// Byzantium gas cost.
+    pub const BYZANTIUM: Bn128PairingPrecompiled = Bn128PairingPrecompiled {
+        base_gas: 100000,
+        pair_gas: 80000,
+    };
+    /// Istanbul gas cost. (EIP1108)
+    pub const ISTANBUL: Bn128PairingPrecompiled = Bn128PairingPrecompiled {
+        base_gas: 45000,
+        pair_gas: 34000,
+    };
+}
 
-pub struct Bn128PairingPrecompiled;
 impl Precompiled for Bn128PairingPrecompiled {
     fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
         use bn::{pairing, AffineG1, AffineG2, Fq, Fq2, Group, Gt, G1, G2};
 
         fn read_one(s: &[u8]) -> Result<(G1, G2), RuntimeError> {
-            let ax = Fq::from_slice(&s[0..32]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-            let ay = Fq::from_slice(&s[32..64]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-            let bay = Fq::from_slice(&s[64..96]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-            let bax = Fq::from_slice(&s[96..128]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-            let bby = Fq::from_slice(&s[128..160]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
-            let bbx = Fq::from_slice(&s[160..192]).map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?;
+            let ax = Fq::from_slice(&s[0..32]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+            let ay = Fq::from_slice(&s[32..64]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+            let bay =
+                Fq::from_slice(&s[64..96]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+            let bax =
+                Fq::from_slice(&s[96..128]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+            let bby =
+                Fq::from_slice(&s[128..160]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
+            let bbx =
+                Fq::from_slice(&s[160..192]).map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?;
 
             let ba = Fq2::new(bax, bay);
             let bb = Fq2::new(bbx, bby);
@@ -114,14 +166,14 @@ impl Precompiled for Bn128PairingPrecompiled {
                 G2::zero()
             } else {
                 AffineG2::new(ba, bb)
-                    .map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?
+                    .map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?
                     .into()
             };
             let a = if ax.is_zero() && ay.is_zero() {
                 G1::zero()
             } else {
                 AffineG1::new(ax, ay)
-                    .map_err(|_| RuntimeError::OnChain(OnChainError::EmptyGas))?
+                    .map_err(|_| RuntimeError::OnChain(OnChainError::PrecompiledFailure))?
                     .into()
             };
 
@@ -129,11 +181,11 @@ impl Precompiled for Bn128PairingPrecompiled {
         }
 
         if data.len() % 192 != 0 {
-            return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
+            return Err(RuntimeError::OnChain(OnChainError::PrecompiledFailure));
         }
 
         let ele_len = data.len() / 192;
-        let gas = Gas::from(80000usize) * Gas::from(ele_len) + Gas::from(100000usize);
+        let gas = Gas::from(self.pair_gas) * Gas::from(ele_len) + Gas::from(self.base_gas);
         if gas > gas_limit {
             return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
         }
@@ -0,0 +1,183 @@
+use bigint::Gas;
+use std::rc::Rc;
+
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInt, One, PrimeField};
+use ark_serialize::CanonicalDeserialize;
+use sha2::{Digest, Sha256};
+
+use evm::errors::{OnChainError, RuntimeError};
+use evm::{Precompiled, VERSIONED_HASH_VERSION_KZG};
+
+pub static KZG_POINT_EVALUATION_PRECOMPILED: KzgPointEvaluationPrecompiled = KzgPointEvaluationPrecompiled;
+
+const G_POINT_EVALUATION: usize = 50000;
+/// Length of the input: versioned hash, evaluation point, claimed value,
+/// commitment and proof.
+const INPUT_LENGTH: usize = 32 + 32 + 32 + 48 + 48;
+const FIELD_ELEMENTS_PER_BLOB: usize = 4096;
+
+/// Modulus of the BLS12-381 scalar field, big-endian.
+const BLS_MODULUS: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05, 0x53, 0xbd, 0xa4,
+    0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// `[τ]G2` of the mainnet KZG trusted setup, in compressed form. It is
+/// the only point of the setup needed to verify a single proof.
+const TRUSTED_SETUP_TAU_G2: [u8; 96] = [
+    0xb5, 0xbf, 0xd7, 0xdd, 0x8c, 0xde, 0xb1, 0x28, 0x84, 0x3b, 0xc2, 0x87, 0x23, 0x0a, 0xf3, 0x89, 0x26, 0x18, 0x70,
+    0x75, 0xcb, 0xfb, 0xef, 0xa8, 0x10, 0x09, 0xa2, 0xce, 0x61, 0x5a, 0xc5, 0x3d, 0x29, 0x14, 0xe5, 0x87, 0x0c, 0xb4,
+    0x52, 0xd2, 0xaf, 0xaa, 0xab, 0x24, 0xf3, 0x49, 0x9f, 0x72, 0x18, 0x5c, 0xbf, 0xee, 0x53, 0x49, 0x27, 0x14, 0x73,
+    0x44, 0x29, 0xb7, 0xb3, 0x86, 0x08, 0xe2, 0x39, 0x26, 0xc9, 0x11, 0xcc, 0xec, 0xea, 0xc9, 0xa3, 0x68, 0x51, 0x47,
+    0x7b, 0xa4, 0xc6, 0x0b, 0x08, 0x70, 0x41, 0xde, 0x62, 0x10, 0x00, 0xed, 0xc9, 0x8e, 0xda, 0xda, 0x20, 0xc1, 0xde,
+    0xf2,
+];
+
+fn invalid_input() -> RuntimeError {
+    RuntimeError::OnChain(OnChainError::PrecompiledFailure)
+}
+
+/// Versioned hash of a KZG commitment, the SHA256 hash with its first
+/// byte replaced by the version.
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> [u8; 32] {
+    let mut sha2 = Sha256::default();
+    sha2.input(commitment);
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&sha2.result());
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+/// Read a big-endian scalar, rejecting values not less than the modulus.
+fn read_fr(data: &[u8]) -> Result<Fr, RuntimeError> {
+    let mut limbs = [0u64; 4];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let start = 32 - (i + 1) * 8;
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&data[start..(start + 8)]);
+        *limb = u64::from_be_bytes(bytes);
+    }
+    Fr::from_bigint(BigInt(limbs)).ok_or_else(invalid_input)
+}
+
+/// Read a compressed G1 point, checking that it is in the subgroup.
+fn read_g1(data: &[u8]) -> Result<G1Affine, RuntimeError> {
+    G1Affine::deserialize_compressed(data).map_err(|_| invalid_input())
+}
+
+/// Check that `proof` opens `commitment` to `y` at `z`, that is
+/// `e(commitment - [y]G1, -G2) * e(proof, [τ]G2 - [z]G2) == 1`.
+fn verify_kzg_proof(commitment: G1Affine, z: Fr, y: Fr, proof: G1Affine) -> bool {
+    let tau = G2Affine::deserialize_compressed_unchecked(&TRUSTED_SETUP_TAU_G2[..]).expect("trusted setup is valid");
+
+    let commitment_minus_y = (commitment.into_group() - G1Affine::generator() * y).into_affine();
+    let tau_minus_z = (tau.into_group() - G2Affine::generator() * z).into_affine();
+
+    Bls12_381::multi_pairing(
+        vec![commitment_minus_y, proof],
+        vec![(-G2Affine::generator().into_group()).into_affine(), tau_minus_z],
+    )
+    .0
+    .is_one()
+}
+
+pub struct KzgPointEvaluationPrecompiled;
+impl Precompiled for KzgPointEvaluationPrecompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let gas = Gas::from(G_POINT_EVALUATION);
+        if gas > gas_limit {
+            return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
+        }
+        if data.len() != INPUT_LENGTH {
+            return Err(invalid_input());
+        }
+
+        let versioned_hash = &data[0..32];
+        let commitment = &data[96..144];
+        if versioned_hash != &kzg_to_versioned_hash(commitment)[..] {
+            return Err(invalid_input());
+        }
+
+        let z = read_fr(&data[32..64])?;
+        let y = read_fr(&data[64..96])?;
+        let commitment = read_g1(commitment)?;
+        let proof = read_g1(&data[144..192])?;
+        if !verify_kzg_proof(commitment, z, y, proof) {
+            return Err(invalid_input());
+        }
+
+        let mut result = vec![0u8; 64];
+        result[24..32].copy_from_slice(&(FIELD_ELEMENTS_PER_BLOB as u64).to_be_bytes());
+        result[32..].copy_from_slice(&BLS_MODULUS);
+
+        Ok((gas, Rc::new(result)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use hexutil::*;
+
+    /// Input opening the commitment to `p(x) = x`, which is `[τ]G1` of
+    /// the trusted setup. The quotient is 1, so the proof is the G1
+    /// generator.
+    fn input(z: u8, y: u8) -> Vec<u8> {
+        let commitment = read_hex(
+            "ad3eb50121139aa34db1d545093ac9374ab7bca2c0f3bf28e27c8dcd8fc7cb42d25926fc0c97b336e9f0fb35e5a04c81",
+        )
+        .unwrap();
+        let proof = read_hex(
+            "97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb",
+        )
+        .unwrap();
+
+        let mut data = kzg_to_versioned_hash(&commitment).to_vec();
+        let mut z_bytes = [0u8; 32];
+        z_bytes[31] = z;
+        data.extend_from_slice(&z_bytes);
+        let mut y_bytes = [0u8; 32];
+        y_bytes[31] = y;
+        data.extend_from_slice(&y_bytes);
+        data.extend_from_slice(&commitment);
+        data.extend_from_slice(&proof);
+        data
+    }
+
+    #[test]
+    fn valid_proof() {
+        let (gas, output) = KZG_POINT_EVALUATION_PRECOMPILED
+            .gas_and_step(&input(5, 5), Gas::from(50000usize))
+            .unwrap();
+        let expected = read_hex("000000000000000000000000000000000000000000000000000000000000100073eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001").unwrap();
+        assert_eq!(gas, Gas::from(50000usize));
+        assert_eq!(expected, Rc::try_unwrap(output).unwrap());
+    }
+
+    #[test]
+    fn invalid_proof() {
+        let limit = Gas::from(50000usize);
+        assert!(KZG_POINT_EVALUATION_PRECOMPILED
+            .gas_and_step(&input(5, 6), limit)
+            .is_err());
+
+        let mut wrong_hash = input(5, 5);
+        wrong_hash[0] = 0x02;
+        assert!(KZG_POINT_EVALUATION_PRECOMPILED
+            .gas_and_step(&wrong_hash, limit)
+            .is_err());
+
+        let mut unreduced = input(5, 5);
+        unreduced[32..64].copy_from_slice(&BLS_MODULUS);
+        assert!(KZG_POINT_EVALUATION_PRECOMPILED
+            .gas_and_step(&unreduced, limit)
+            .is_err());
+
+        assert!(KZG_POINT_EVALUATION_PRECOMPILED
+            .gas_and_step(&input(5, 5), Gas::from(49999usize))
+            .is_err());
+    }
+}
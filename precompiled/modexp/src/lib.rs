@@ -4,68 +4,263 @@ use std::rc::Rc;
 use evm::errors::{NotSupportedError, OnChainError, RuntimeError};
 use evm::Precompiled;
 
-pub static MODEXP_PRECOMPILED: ModexpPrecompiled = ModexpPrecompiled;
+mod montgomery;
 
-pub struct ModexpPrecompiled;
-impl Precompiled for ModexpPrecompiled {
-    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
-        use num_bigint::BigUint;
-        use std::cmp;
-
-        fn adjusted_exponent_length(exponent_length: U256, base_length: U256, data: &[u8]) -> U256 {
-            let mut exp32_arr = Vec::new();
-            for i in 0..32 {
-                if U256::from(96) + base_length + U256::from(i) >= U256::from(data.len()) {
-                    exp32_arr.push(0u8);
-                } else {
-                    let base_length_usize: usize = base_length.as_usize();
-                    let data_i: usize = 96 + base_length_usize + i;
-                    exp32_arr.push(data[data_i]);
-                }
+pub static MODEXP_PRECOMPILED: ModexpPrecompiled = ModexpPrecompiled::BYZANTIUM;
+pub static BERLIN_MODEXP_PRECOMPILED: ModexpPrecompiled = ModexpPrecompiled::BERLIN;
+pub static OSAKA_MODEXP_PRECOMPILED: ModexpPrecompiled = ModexpPrecompiled::OSAKA;
+
+/// Gas pricing formula of the modexp precompiled contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModexpPricing {
+    /// Original pricing. (EIP198)
+    Eip198,
+    /// Repricing with a 200 gas minimum. (EIP2565)
+    Eip2565,
+    /// Repricing with a 500 gas minimum. (EIP7883)
+    Eip7883,
+}
+
+/// Modular exponentiation precompiled contract, with a configurable
+/// pricing formula and input bound.
+pub struct ModexpPrecompiled {
+    /// Gas pricing formula.
+    pub pricing: ModexpPricing,
+    /// Maximum length in bytes of the base, exponent and modulus, if
+    /// any. (EIP7823)
+    pub input_limit: Option<usize>,
+}
+
+impl ModexpPrecompiled {
+    /// Byzantium modexp.
+    pub const BYZANTIUM: ModexpPrecompiled = ModexpPrecompiled {
+        pricing: ModexpPricing::Eip198,
+        input_limit: None,
+    };
+    /// Berlin modexp. (EIP2565)
+    pub const BERLIN: ModexpPrecompiled = ModexpPrecompiled {
+        pricing: ModexpPricing::Eip2565,
+        input_limit: None,
+    };
+    /// Osaka modexp. (EIP7823, EIP7883)
+    pub const OSAKA: ModexpPrecompiled = ModexpPrecompiled {
+        pricing: ModexpPricing::Eip7883,
+        input_limit: Some(1024),
+    };
+}
+
+fn read_length(data: &[u8], offset: usize) -> U256 {
+    U256::from(&data[offset..(offset + 32)])
+}
+
+/// The first `min(exponent_length, 32)` bytes of the exponent, padded
+/// with zeros past the end of the input.
+fn exponent_head(exponent_length: U256, base_length: U256, data: &[u8]) -> U256 {
+    if base_length >= U256::from(data.len()) {
+        return U256::zero();
+    }
+    let start = 96 + base_length.as_usize();
+    let len = if exponent_length < U256::from(32) {
+        exponent_length.as_usize()
+    } else {
+        32
+    };
+
+    let mut head = [0u8; 32];
+    for i in 0..len {
+        if start + i < data.len() {
+            head[32 - len + i] = data[start + i];
+        }
+    }
+    U256::from(&head[..])
+}
+
+fn byzantium_gas(
+    base_length: U256,
+    exponent_length: U256,
+    modulus_length: U256,
+    data: &[u8],
+) -> Result<U256, RuntimeError> {
+    use std::cmp;
+
+    fn adjusted_exponent_length(exponent_length: U256, base_length: U256, data: &[u8]) -> U256 {
+        let mut exp32_arr = Vec::new();
+        for i in 0..32 {
+            if U256::from(96) + base_length + U256::from(i) >= U256::from(data.len()) {
+                exp32_arr.push(0u8);
+            } else {
+                let base_length_usize: usize = base_length.as_usize();
+                let data_i: usize = 96 + base_length_usize + i;
+                exp32_arr.push(data[data_i]);
             }
-            let exp32 = U256::from(exp32_arr.as_slice());
+        }
+        let exp32 = U256::from(exp32_arr.as_slice());
 
-            if exponent_length <= U256::from(32) && exp32 == U256::zero() {
-                U256::zero()
-            } else if exponent_length <= U256::from(32) {
-                U256::from(exp32.bits())
+        if exponent_length <= U256::from(32) && exp32 == U256::zero() {
+            U256::zero()
+        } else if exponent_length <= U256::from(32) {
+            U256::from(exp32.bits())
+        } else {
+            U256::from(8) * (exponent_length - U256::from(32)) + U256::from(exp32.bits())
+        }
+    }
+
+    fn mult_complexity(x: U256) -> Result<U256, RuntimeError> {
+        if x <= U256::from(64) {
+            Ok(x * x)
+        } else if x <= U256::from(1024) {
+            Ok(x * x / U256::from(4) + U256::from(96) * x - U256::from(3072))
+        } else {
+            let (sqr, o) = x.overflowing_mul(x);
+            if o {
+                Err(RuntimeError::OnChain(OnChainError::EmptyGas))
             } else {
-                U256::from(8) * (exponent_length - U256::from(32)) + U256::from(exp32.bits())
+                Ok(sqr / U256::from(16) + U256::from(480) * x - U256::from(199680))
             }
         }
+    }
+
+    let op1 = mult_complexity(cmp::max(modulus_length, base_length))?;
+    let ael = adjusted_exponent_length(exponent_length, base_length, data);
+    let op2 = cmp::max(ael, U256::from(1)) / U256::from(20);
+    let (r, o) = op1.overflowing_mul(op2);
+    if o {
+        return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
+    }
+    Ok(r)
+}
+
+/// Gas cost of the repriced formulas. EIP2565 divides the product of
+/// the multiplication complexity and the iteration count by 3, while
+/// EIP7883 doubles the complexity of large inputs, raises the cost of
+/// long exponents and drops the divisor.
+fn repriced_gas(
+    pricing: ModexpPricing,
+    base_length: U256,
+    exponent_length: U256,
+    modulus_length: U256,
+    data: &[u8],
+) -> Result<U256, RuntimeError> {
+    use std::cmp;
+
+    let max_length = cmp::max(base_length, modulus_length);
+    let words = max_length / U256::from(8)
+        + if max_length % U256::from(8) == U256::zero() {
+            U256::zero()
+        } else {
+            U256::one()
+        };
+    let (mut complexity, o) = words.overflowing_mul(words);
+    if o {
+        return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
+    }
 
-        fn mult_complexity(x: U256) -> Result<U256, RuntimeError> {
-            if x <= U256::from(64) {
-                Ok(x * x)
-            } else if x <= U256::from(1024) {
-                Ok(x * x / U256::from(4) + U256::from(96) * x - U256::from(3072))
+    let exponent_multiplier = match pricing {
+        ModexpPricing::Eip7883 => {
+            complexity = if max_length <= U256::from(32) {
+                U256::from(16)
             } else {
-                let (sqr, o) = x.overflowing_mul(x);
+                let (c, o) = complexity.overflowing_mul(U256::from(2));
                 if o {
-                    Err(RuntimeError::OnChain(OnChainError::EmptyGas))
-                } else {
-                    Ok(sqr / U256::from(16) + U256::from(480) * x - U256::from(199680))
+                    return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
                 }
-            }
+                c
+            };
+            16
         }
+        _ => 8,
+    };
+
+    let head = exponent_head(exponent_length, base_length, data);
+    let head_bits = if head == U256::zero() {
+        U256::zero()
+    } else {
+        U256::from(head.bits() - 1)
+    };
+    let iteration_count = if exponent_length <= U256::from(32) {
+        head_bits
+    } else {
+        let (c, o1) = (exponent_length - U256::from(32)).overflowing_mul(U256::from(exponent_multiplier));
+        let (c, o2) = c.overflowing_add(head_bits);
+        if o1 || o2 {
+            return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
+        }
+        c
+    };
+    let iteration_count = cmp::max(iteration_count, U256::one());
+
+    let (r, o) = complexity.overflowing_mul(iteration_count);
+    if o {
+        return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
+    }
+    Ok(match pricing {
+        ModexpPricing::Eip7883 => cmp::max(r, U256::from(500)),
+        _ => cmp::max(r / U256::from(3), U256::from(200)),
+    })
+}
+
+/// `base^exponent mod modulus` with generic big integers, as
+/// `modulus.len()` big-endian bytes.
+fn bigint_modexp(base: &[u8], exponent: &[u8], modulus: &[u8]) -> Vec<u8> {
+    use num_bigint::BigUint;
 
+    let base = BigUint::from_bytes_be(base);
+    let exponent = BigUint::from_bytes_be(exponent);
+    let modulus_length = modulus.len();
+    let modulus = BigUint::from_bytes_be(modulus);
+
+    let mut result = base.modpow(&exponent, &modulus).to_bytes_be();
+    assert!(result.len() <= modulus_length);
+    while result.len() < modulus_length {
+        result.insert(0, 0u8);
+    }
+    result
+}
+
+/// `base^exponent mod modulus`, as `modulus.len()` big-endian bytes.
+/// Odd moduli of up to 2048 bits use fixed-width Montgomery
+/// multiplication, and the others fall back to generic big integers.
+fn modexp(base: &[u8], exponent: &[u8], modulus: &[u8]) -> Vec<u8> {
+    let significant = match modulus.iter().position(|byte| *byte != 0) {
+        Some(start) => &modulus[start..],
+        None => return vec![0u8; modulus.len()],
+    };
+    if significant[significant.len() - 1] & 1 == 0 {
+        return bigint_modexp(base, exponent, modulus);
+    }
+
+    match significant.len() {
+        0..=32 => montgomery::modexp::<4>(base, exponent, significant, modulus.len()),
+        33..=64 => montgomery::modexp::<8>(base, exponent, significant, modulus.len()),
+        65..=128 => montgomery::modexp::<16>(base, exponent, significant, modulus.len()),
+        129..=256 => montgomery::modexp::<32>(base, exponent, significant, modulus.len()),
+        _ => bigint_modexp(base, exponent, modulus),
+    }
+}
+
+impl Precompiled for ModexpPrecompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
         // Padding data to be at least 32 * 3 bytes.
         let mut data: Vec<u8> = data.into();
         while data.len() < 32 * 3 {
             data.push(0);
         }
 
-        let base_length = U256::from(&data[0..32]);
-        let exponent_length = U256::from(&data[32..64]);
-        let modulus_length = U256::from(&data[64..96]);
+        let base_length = read_length(&data, 0);
+        let exponent_length = read_length(&data, 32);
+        let modulus_length = read_length(&data, 64);
 
-        let op1 = mult_complexity(cmp::max(modulus_length, base_length))?;
-        let ael = adjusted_exponent_length(exponent_length, base_length, &data);
-        let op2 = cmp::max(ael, U256::from(1)) / U256::from(20);
-        let (r, o) = op1.overflowing_mul(op2);
-        if o {
-            return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
+        if let Some(limit) = self.input_limit {
+            let limit = U256::from(limit);
+            if base_length > limit || exponent_length > limit || modulus_length > limit {
+                return Err(RuntimeError::OnChain(OnChainError::PrecompiledFailure));
+            }
         }
+
+        let r = match self.pricing {
+            ModexpPricing::Eip198 => byzantium_gas(base_length, exponent_length, modulus_length, &data)?,
+            pricing => repriced_gas(pricing, base_length, exponent_length, modulus_length, &data)?,
+        };
         let gas: Gas = r.into();
 
         if gas > gas_limit {
@@ -109,15 +304,7 @@ impl Precompiled for ModexpPrecompiled {
             }
         }
 
-        let base = BigUint::from_bytes_be(&base_arr);
-        let exponent = BigUint::from_bytes_be(&exponent_arr);
-        let modulus = BigUint::from_bytes_be(&modulus_arr);
-
-        let mut result = base.modpow(&exponent, &modulus).to_bytes_be();
-        assert!(result.len() <= modulus_length);
-        while result.len() < modulus_length {
-            result.insert(0, 0u8);
-        }
+        let result = modexp(&base_arr, &exponent_arr, &modulus_arr);
 
         Ok((gas, Rc::new(result)))
     }
@@ -128,9 +315,40 @@ mod tests {
     use crate::*;
     use hexutil::*;
 
+    /// Check the result against the generic big integer implementation
+    /// for the arguments of a call input.
+    fn differential(input: &[u8]) {
+        let mut data = input.to_vec();
+        data.resize(96, 0);
+        let base_length = read_length(&data, 0).as_usize();
+        let exponent_length = read_length(&data, 32).as_usize();
+        let modulus_length = read_length(&data, 64).as_usize();
+        data.resize(96 + base_length + exponent_length + modulus_length, 0);
+
+        let (base, rest) = data[96..].split_at(base_length);
+        let (exponent, modulus) = rest.split_at(exponent_length);
+        differential_arguments(base, exponent, modulus);
+    }
+
+    fn differential_arguments(base: &[u8], exponent: &[u8], modulus: &[u8]) {
+        if modulus.iter().all(|byte| *byte == 0) {
+            assert_eq!(modexp(base, exponent, modulus), vec![0u8; modulus.len()]);
+        } else {
+            assert_eq!(
+                modexp(base, exponent, modulus),
+                bigint_modexp(base, exponent, modulus),
+                "base {:?}, exponent {:?}, modulus {:?}",
+                base,
+                exponent,
+                modulus
+            );
+        }
+    }
+
     #[test]
     fn spec_test1() {
         let input = read_hex("00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000002003fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2efffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f").unwrap();
+        differential(&input);
         let (_, output) = MODEXP_PRECOMPILED
             .gas_and_step(&input, Gas::from(10000000usize))
             .unwrap();
@@ -141,6 +359,7 @@ mod tests {
     #[test]
     fn spec_test2() {
         let input = read_hex("000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000020fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2efffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f").unwrap();
+        differential(&input);
         let (_, output) = MODEXP_PRECOMPILED
             .gas_and_step(&input, Gas::from(10000000usize))
             .unwrap();
@@ -160,6 +379,7 @@ mod tests {
     #[test]
     fn spec_test4() {
         let input = read_hex("00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000002003ffff800000000000000000000000000000000000000000000000000000000000000007").unwrap();
+        differential(&input);
         let (_, output) = MODEXP_PRECOMPILED
             .gas_and_step(&input, Gas::from(10000000usize))
             .unwrap();
@@ -170,10 +390,79 @@ mod tests {
     #[test]
     fn sepc_test5() {
         let input = read_hex("00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000002003ffff80").unwrap();
+        differential(&input);
         let (_, output) = MODEXP_PRECOMPILED
             .gas_and_step(&input, Gas::from(10000000usize))
             .unwrap();
         let expected = read_hex("3b01b01ac41f2d6e917c6d6a221ce793802469026d9ab7578fa2e79e4da6aaab").unwrap();
         assert_eq!(expected, Rc::try_unwrap(output).unwrap());
     }
+
+    fn spec_gas(precompiled: &ModexpPrecompiled, input: &str) -> Gas {
+        let input = read_hex(input).unwrap();
+        precompiled.gas_and_step(&input, Gas::from(10000000usize)).unwrap().0
+    }
+
+    #[test]
+    fn repriced_gas() {
+        let example1 = "00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000002003fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2efffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+        let zero_exponent = "000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000001020003";
+
+        assert_eq!(spec_gas(&BERLIN_MODEXP_PRECOMPILED, example1), Gas::from(1360usize));
+        assert_eq!(spec_gas(&BERLIN_MODEXP_PRECOMPILED, zero_exponent), Gas::from(200usize));
+        assert_eq!(spec_gas(&OSAKA_MODEXP_PRECOMPILED, example1), Gas::from(4080usize));
+        assert_eq!(spec_gas(&OSAKA_MODEXP_PRECOMPILED, zero_exponent), Gas::from(500usize));
+    }
+
+    #[test]
+    fn input_limit() {
+        let mut input = read_hex("000000000000000000000000000000000000000000000000000000000000040100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001").unwrap();
+        input.extend_from_slice(&[0u8; 1025]);
+        input.push(3);
+        assert!(BERLIN_MODEXP_PRECOMPILED
+            .gas_and_step(&input, Gas::from(10000000usize))
+            .is_ok());
+        match OSAKA_MODEXP_PRECOMPILED.gas_and_step(&input, Gas::from(10000000usize)) {
+            Err(RuntimeError::OnChain(OnChainError::PrecompiledFailure)) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn differential_generated() {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut bytes = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect()
+        };
+
+        for modulus_length in (1..40).chain(vec![63, 64, 65, 127, 128, 129, 255, 256, 257, 300]) {
+            for round in 0..4 {
+                let base = bytes(modulus_length + round * 7);
+                let exponent = bytes(round * 3);
+                let mut modulus = bytes(modulus_length);
+                if round % 2 == 0 {
+                    modulus[modulus_length - 1] |= 1;
+                }
+                differential_arguments(&base, &exponent, &modulus);
+
+                let mut padded = vec![0u8; 5];
+                padded.extend_from_slice(&modulus);
+                differential_arguments(&base, &exponent, &padded);
+                differential_arguments(&modulus, &exponent, &modulus);
+            }
+        }
+
+        differential_arguments(&[7], &[3], &[1]);
+        differential_arguments(&[7], &[], &[0, 1]);
+        differential_arguments(&[], &[], &[0x0d]);
+        differential_arguments(&[0xff; 64], &[0xff; 32], &[0xff; 32]);
+        differential_arguments(&[2], &[5], &[0, 0]);
+    }
 }
@@ -0,0 +1,197 @@
+//! Modular exponentiation with fixed-width Montgomery multiplication.
+//!
+//! Numbers are `N` little-endian 64-bit limbs and the Montgomery radix
+//! is `R = 2^(64 * N)`, so any odd modulus below `R` is supported.
+
+/// Width in bits of the exponent window.
+const WINDOW_BITS: usize = 4;
+
+/// `a + b * c + carry`, as the low limb and the carry.
+#[inline(always)]
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let r = u128::from(a) + u128::from(b) * u128::from(c) + u128::from(carry);
+    (r as u64, (r >> 64) as u64)
+}
+
+/// `a - b`, returning whether it borrowed.
+fn sub_assign<const N: usize>(a: &mut [u64; N], b: &[u64; N]) -> bool {
+    let mut borrow = false;
+    for i in 0..N {
+        let (d, b1) = a[i].overflowing_sub(b[i]);
+        let (d, b2) = d.overflowing_sub(borrow as u64);
+        a[i] = d;
+        borrow = b1 || b2;
+    }
+    borrow
+}
+
+fn less_than<const N: usize>(a: &[u64; N], b: &[u64; N]) -> bool {
+    for i in (0..N).rev() {
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+    }
+    false
+}
+
+/// Read a big-endian number of at most `8 * N` bytes.
+fn from_be_bytes<const N: usize>(bytes: &[u8]) -> [u64; N] {
+    debug_assert!(bytes.len() <= 8 * N);
+    let mut limbs = [0u64; N];
+    for (i, byte) in bytes.iter().rev().enumerate() {
+        limbs[i / 8] |= u64::from(*byte) << (8 * (i % 8));
+    }
+    limbs
+}
+
+struct Montgomery<const N: usize> {
+    modulus: [u64; N],
+    /// `-modulus^-1 mod 2^64`.
+    inv: u64,
+    /// `R mod modulus`, one in Montgomery form.
+    one: [u64; N],
+    /// `R^2 mod modulus`.
+    r2: [u64; N],
+}
+
+impl<const N: usize> Montgomery<N> {
+    fn new(modulus: [u64; N]) -> Self {
+        debug_assert!(modulus[0] & 1 == 1);
+
+        // Newton iteration, each step doubling the correct low bits.
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(modulus[0].wrapping_mul(inv)));
+        }
+
+        let mut field = Montgomery {
+            modulus,
+            inv: inv.wrapping_neg(),
+            one: [0u64; N],
+            r2: [0u64; N],
+        };
+
+        let mut x = [0u64; N];
+        x[0] = 1;
+        if !less_than(&x, &modulus) {
+            x[0] = 0;
+        }
+        for _ in 0..(64 * N) {
+            x = field.add(&x, &x);
+        }
+        field.one = x;
+        for _ in 0..(64 * N) {
+            x = field.add(&x, &x);
+        }
+        field.r2 = x;
+        field
+    }
+
+    /// `a + b mod modulus`, for `a` and `b` less than the modulus.
+    fn add(&self, a: &[u64; N], b: &[u64; N]) -> [u64; N] {
+        let mut r = [0u64; N];
+        let mut carry = false;
+        for i in 0..N {
+            let (s, c1) = a[i].overflowing_add(b[i]);
+            let (s, c2) = s.overflowing_add(carry as u64);
+            r[i] = s;
+            carry = c1 || c2;
+        }
+        if carry || !less_than(&r, &self.modulus) {
+            sub_assign(&mut r, &self.modulus);
+        }
+        r
+    }
+
+    /// `a * b / R mod modulus`, fully reduced as long as `a * b` is
+    /// less than `modulus * R`. (CIOS)
+    fn mul(&self, a: &[u64; N], b: &[u64; N]) -> [u64; N] {
+        let mut t = [0u64; N];
+        let mut t_n = 0u64;
+
+        for b_i in b.iter() {
+            let mut carry = 0;
+            for j in 0..N {
+                let (l, c) = mac(t[j], a[j], *b_i, carry);
+                t[j] = l;
+                carry = c;
+            }
+            let (s, overflow) = t_n.overflowing_add(carry);
+            t_n = s;
+            let t_n1 = overflow as u64;
+
+            let m = t[0].wrapping_mul(self.inv);
+            let (_, mut carry) = mac(t[0], m, self.modulus[0], 0);
+            for j in 1..N {
+                let (l, c) = mac(t[j], m, self.modulus[j], carry);
+                t[j - 1] = l;
+                carry = c;
+            }
+            let (s, overflow) = t_n.overflowing_add(carry);
+            t[N - 1] = s;
+            t_n = t_n1 + overflow as u64;
+        }
+
+        if t_n != 0 || !less_than(&t, &self.modulus) {
+            sub_assign(&mut t, &self.modulus);
+        }
+        t
+    }
+
+    /// Montgomery form of a big-endian number of any length, reduced
+    /// `N` limbs at a time from the most significant end.
+    fn encode(&self, bytes: &[u8]) -> [u64; N] {
+        let chunk = 8 * N;
+        let head = bytes.len() % chunk;
+        let mut acc = [0u64; N];
+        if head != 0 {
+            acc = self.mul(&from_be_bytes(&bytes[..head]), &self.r2);
+        }
+        for part in bytes[head..].chunks(chunk) {
+            acc = self.mul(&acc, &self.r2);
+            acc = self.add(&acc, &self.mul(&from_be_bytes(part), &self.r2));
+        }
+        acc
+    }
+
+    fn decode(&self, a: &[u64; N]) -> [u64; N] {
+        let mut one = [0u64; N];
+        one[0] = 1;
+        self.mul(a, &one)
+    }
+}
+
+/// `base^exponent mod modulus` for an odd modulus of at most `8 * N`
+/// bytes, as `length` big-endian bytes.
+pub fn modexp<const N: usize>(base: &[u8], exponent: &[u8], modulus: &[u8], length: usize) -> Vec<u8> {
+    let field = Montgomery::<N>::new(from_be_bytes(modulus));
+
+    let mut table = [field.one; 1 << WINDOW_BITS];
+    table[1] = field.encode(base);
+    for i in 2..table.len() {
+        table[i] = field.mul(&table[i - 1], &table[1]);
+    }
+
+    let mut acc = field.one;
+    let mut started = false;
+    for byte in exponent {
+        for window in &[byte >> WINDOW_BITS, byte & ((1 << WINDOW_BITS) - 1)] {
+            if started {
+                for _ in 0..WINDOW_BITS {
+                    acc = field.mul(&acc, &acc);
+                }
+            }
+            if *window != 0 {
+                acc = field.mul(&acc, &table[*window as usize]);
+                started = true;
+            }
+        }
+    }
+
+    let result = field.decode(&acc);
+    let mut output = vec![0u8; length];
+    for (i, byte) in output.iter_mut().rev().take(8 * N).enumerate() {
+        *byte = (result[i / 8] >> (8 * (i % 8))) as u8;
+    }
+    output
+}
@@ -0,0 +1,153 @@
+use bigint::{Address, Gas, H160};
+use std::rc::Rc;
+
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::EncodedPoint;
+
+use evm::errors::{OnChainError, RuntimeError};
+use evm::Precompiled;
+
+/// Address the precompiled contract is deployed at by RIP7212 and
+/// EIP7951. Patches may register it at any other address.
+pub const P256VERIFY_ADDRESS: Address = H160([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00]);
+
+pub static P256VERIFY_PRECOMPILED: P256VerifyPrecompiled = P256VerifyPrecompiled::RIP7212;
+pub static OSAKA_P256VERIFY_PRECOMPILED: P256VerifyPrecompiled = P256VerifyPrecompiled::EIP7951;
+
+/// Length of the input: message hash, signature `r` and `s`, and public
+/// key `x` and `y`.
+const INPUT_LENGTH: usize = 32 * 5;
+
+/// secp256r1 signature verification, charging a constant gas cost. An
+/// input of the wrong length or an invalid signature returns empty
+/// output instead of failing.
+pub struct P256VerifyPrecompiled {
+    /// Gas cost of a verification.
+    pub gas: usize,
+}
+
+impl P256VerifyPrecompiled {
+    /// Rollup gas cost. (RIP7212)
+    pub const RIP7212: P256VerifyPrecompiled = P256VerifyPrecompiled { gas: 3450 };
+    /// Mainnet gas cost. (EIP7951)
+    pub const EIP7951: P256VerifyPrecompiled = P256VerifyPrecompiled { gas: 6900 };
+}
+
+fn verify(data: &[u8]) -> bool {
+    if data.len() != INPUT_LENGTH {
+        return false;
+    }
+
+    let signature = match Signature::from_slice(&data[32..96]) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    let point = EncodedPoint::from_affine_coordinates(data[96..128].into(), data[128..160].into(), false);
+    let key = match VerifyingKey::from_encoded_point(&point) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+
+    key.verify_prehash(&data[..32], &signature).is_ok()
+}
+
+impl Precompiled for P256VerifyPrecompiled {
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let gas = Gas::from(self.gas);
+        if gas > gas_limit {
+            return Err(RuntimeError::OnChain(OnChainError::EmptyGas));
+        }
+
+        if verify(data) {
+            let mut result = vec![0u8; 32];
+            result[31] = 1;
+            Ok((gas, Rc::new(result)))
+        } else {
+            Ok((gas, Rc::new(Vec::new())))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use p256::ecdsa::signature::hazmat::PrehashSigner;
+    use p256::ecdsa::SigningKey;
+
+    fn input(hash: [u8; 32]) -> Vec<u8> {
+        let key = SigningKey::from_slice(&[0x42u8; 32]).unwrap();
+        let signature: Signature = key.sign_prehash(&hash).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+
+        let mut data = hash.to_vec();
+        data.extend_from_slice(&signature.to_bytes());
+        data.extend_from_slice(point.x().unwrap());
+        data.extend_from_slice(point.y().unwrap());
+        data
+    }
+
+    #[test]
+    fn valid_signature() {
+        let (gas, output) = P256VERIFY_PRECOMPILED
+            .gas_and_step(&input([0x11; 32]), Gas::from(3450usize))
+            .unwrap();
+        let mut expected = vec![0u8; 32];
+        expected[31] = 1;
+        assert_eq!(gas, Gas::from(3450usize));
+        assert_eq!(expected, Rc::try_unwrap(output).unwrap());
+
+        let (gas, _) = OSAKA_P256VERIFY_PRECOMPILED
+            .gas_and_step(&input([0x11; 32]), Gas::from(6900usize))
+            .unwrap();
+        assert_eq!(gas, Gas::from(6900usize));
+    }
+
+    #[test]
+    fn malleable_signature() {
+        let mut data = input([0x11; 32]);
+        let (r, s) = Signature::from_slice(&data[32..96]).unwrap().split_scalars();
+        let flipped = Signature::from_scalars(r, -s).unwrap();
+        data[32..96].copy_from_slice(&flipped.to_bytes());
+
+        let (_, output) = P256VERIFY_PRECOMPILED
+            .gas_and_step(&data, Gas::from(3450usize))
+            .unwrap();
+        assert_eq!(output[31], 1);
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let limit = Gas::from(3450usize);
+
+        let mut wrong_hash = input([0x11; 32]);
+        wrong_hash[0] ^= 1;
+        let (gas, output) = P256VERIFY_PRECOMPILED.gas_and_step(&wrong_hash, limit).unwrap();
+        assert_eq!(gas, Gas::from(3450usize));
+        assert!(output.is_empty());
+
+        let mut long = input([0x11; 32]);
+        long.push(0);
+        assert!(P256VERIFY_PRECOMPILED.gas_and_step(&long, limit).unwrap().1.is_empty());
+
+        let mut off_curve = input([0x11; 32]);
+        off_curve[159] ^= 1;
+        assert!(P256VERIFY_PRECOMPILED
+            .gas_and_step(&off_curve, limit)
+            .unwrap()
+            .1
+            .is_empty());
+
+        let mut zero_r = input([0x11; 32]);
+        zero_r[32..64].copy_from_slice(&[0u8; 32]);
+        assert!(P256VERIFY_PRECOMPILED
+            .gas_and_step(&zero_r, limit)
+            .unwrap()
+            .1
+            .is_empty());
+
+        assert!(P256VERIFY_PRECOMPILED
+            .gas_and_step(&input([0x11; 32]), Gas::from(3449usize))
+            .is_err());
+    }
+}
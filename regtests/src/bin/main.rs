@@ -19,7 +19,10 @@ fn from_rpc_block(block: &RPCBlock) -> HeaderParams {
         timestamp: U256::from_str(&block.timestamp).unwrap().into(),
         number: U256::from_str(&block.number.as_ref().unwrap()).unwrap(),
         difficulty: U256::from_str(&block.difficulty).unwrap(),
+        prev_randao: H256::default(),
         gas_limit: Gas::from_str(&block.gas_limit).unwrap(),
+        base_fee: Gas::zero(),
+        excess_blob_gas: Gas::zero(),
     }
 }
 
@@ -34,8 +37,13 @@ fn from_rpc_transaction(transaction: &RPCTransaction) -> ValidTransaction {
         value: U256::from_str(&transaction.value).unwrap(),
         gas_limit: Gas::from_str(&transaction.gas).unwrap(),
         gas_price: Gas::from_str(&transaction.gas_price).unwrap(),
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
         input: Rc::new(read_hex(&transaction.input).unwrap()),
         nonce: U256::from_str(&transaction.nonce).unwrap(),
+        access_list: Vec::new(),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: Rc::new(Vec::new()),
     }
 }
 
@@ -162,7 +170,7 @@ fn test_block<T: GethRPCClient, P: Patch + Default + Clone>(client: &mut T, numb
         let mut vm = if let Some(last_vm) = last_vm.take() {
             SeqTransactionVM::with_previous(transaction, block_header.clone(), &last_vm)
         } else {
-            SeqTransactionVM::new(&patch, transaction, block_header.clone())
+            SeqTransactionVM::new(&patch, transaction, block_header.clone()).unwrap()
         };
 
         handle_fire(client, &mut vm, last_id);
@@ -202,6 +202,10 @@ pub struct AccountState<'a, A: AccountPatch> {
     accounts: Map<Address, AccountChange>,
     orig_storage: RefCell<Map<Address, Storage>>,
     codes: Map<Address, Rc<Vec<u8>>>,
+    accessed_addresses: Set<Address>,
+    accessed_storage: Set<(Address, U256)>,
+    transient_storage: Map<(Address, U256), M256>,
+    created: Set<Address>,
     account_patch: &'a A,
 }
 
@@ -212,6 +216,10 @@ impl<'a, A: AccountPatch> AccountState<'a, A> {
             accounts: Map::new(),
             codes: Map::new(),
             orig_storage: RefCell::new(Map::new()),
+            accessed_addresses: Set::new(),
+            accessed_storage: Set::new(),
+            transient_storage: Map::new(),
+            created: Set::new(),
             account_patch,
         }
     }
@@ -222,6 +230,10 @@ impl<'a, A: AccountPatch> AccountState<'a, A> {
             accounts: prev.accounts.clone(),
             orig_storage: prev.orig_storage.clone(),
             codes: prev.codes.clone(),
+            accessed_addresses: prev.accessed_addresses.clone(),
+            accessed_storage: prev.accessed_storage.clone(),
+            transient_storage: prev.transient_storage.clone(),
+            created: prev.created.clone(),
             account_patch,
         }
     }
@@ -233,6 +245,10 @@ impl<'a, A: AccountPatch> Clone for AccountState<'a, A> {
             accounts: self.accounts.clone(),
             orig_storage: self.orig_storage.clone(),
             codes: self.codes.clone(),
+            accessed_addresses: self.accessed_addresses.clone(),
+            accessed_storage: self.accessed_storage.clone(),
+            transient_storage: self.transient_storage.clone(),
+            created: self.created.clone(),
             account_patch: self.account_patch,
         }
     }
@@ -635,6 +651,7 @@ impl<'a, A: AccountPatch> AccountState<'a, A> {
 
         self.codes.remove(&address);
         self.insert_account(account);
+        self.created.insert(address);
 
         Ok(())
     }
@@ -774,4 +791,93 @@ impl<'a, A: AccountPatch> AccountState<'a, A> {
         self.insert_account(AccountChange::Nonexist(address));
         Ok(())
     }
+
+    /// Whether the address has been accessed in the current
+    /// transaction. (EIP2929)
+    pub fn is_address_accessed(&self, address: Address) -> bool {
+        self.accessed_addresses.contains(&address)
+    }
+
+    /// Mark the address as accessed in the current transaction.
+    pub fn access_address(&mut self, address: Address) {
+        self.accessed_addresses.insert(address);
+    }
+
+    /// Whether the storage slot has been accessed in the current
+    /// transaction. (EIP2929)
+    pub fn is_storage_accessed(&self, address: Address, index: U256) -> bool {
+        self.accessed_storage.contains(&(address, index))
+    }
+
+    /// Mark the storage slot as accessed in the current transaction.
+    pub fn access_storage(&mut self, address: Address, index: U256) {
+        self.accessed_storage.insert((address, index));
+    }
+
+    /// Forget all accessed addresses and storage slots. This is done
+    /// at the start of every transaction.
+    pub fn clear_accessed(&mut self) {
+        self.accessed_addresses.clear();
+        self.accessed_storage.clear();
+    }
+
+    /// Read a value from the transient storage of an account. Unset
+    /// slots read as zero. (EIP1153)
+    pub fn transient_storage_read(&self, address: Address, index: U256) -> M256 {
+        self.transient_storage
+            .get(&(address, index))
+            .cloned()
+            .unwrap_or_else(M256::zero)
+    }
+
+    /// Write a value to the transient storage of an account. Unlike
+    /// storage, it is never committed to the accounts. (EIP1153)
+    pub fn transient_storage_write(&mut self, address: Address, index: U256, value: M256) {
+        if value == M256::zero() {
+            self.transient_storage.remove(&(address, index));
+        } else {
+            self.transient_storage.insert((address, index), value);
+        }
+    }
+
+    /// Discard all transient storage. This is done at the start of
+    /// every transaction.
+    pub fn clear_transient_storage(&mut self) {
+        self.transient_storage.clear();
+    }
+
+    /// Whether the account was created in the current
+    /// transaction. (EIP6780)
+    pub fn is_created(&self, address: Address) -> bool {
+        self.created.contains(&address)
+    }
+
+    /// Forget all accounts created. This is done at the start of
+    /// every transaction.
+    pub fn clear_created(&mut self) {
+        self.created.clear();
+    }
+}
+
+/// Read-only view of an account state. If the search failed, the
+/// methods return a `RequireError`.
+pub trait AccountStateRead {
+    /// Find code by its address.
+    fn code(&self, address: Address) -> Result<Rc<Vec<u8>>, RequireError>;
+    /// Find nonce by its address.
+    fn nonce(&self, address: Address) -> Result<U256, RequireError>;
+    /// Find balance by its address.
+    fn balance(&self, address: Address) -> Result<U256, RequireError>;
+    /// Read a value from an account storage.
+    fn storage_read(&self, address: Address, index: U256) -> Result<M256, RequireError>;
+}
+
+#[rustfmt::skip]
+impl<'a, A: AccountPatch> AccountStateRead for AccountState<'a, A> {
+    fn code(&self, address: Address) -> Result<Rc<Vec<u8>>, RequireError> { AccountState::code(self, address) }
+    fn nonce(&self, address: Address) -> Result<U256, RequireError> { AccountState::nonce(self, address) }
+    fn balance(&self, address: Address) -> Result<U256, RequireError> { AccountState::balance(self, address) }
+    fn storage_read(&self, address: Address, index: U256) -> Result<M256, RequireError> {
+        AccountState::storage_read(self, address, index)
+    }
 }
@@ -3,5 +3,5 @@
 mod account;
 mod blockhash;
 
-pub use self::account::{AccountChange, AccountCommitment, AccountState, Storage};
+pub use self::account::{AccountChange, AccountCommitment, AccountState, AccountStateRead, Storage};
 pub use self::blockhash::BlockhashState;
@@ -0,0 +1,498 @@
+//! EVM Object Format (EOF) container parsing and validation.
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use crate::errors::EofError;
+
+/// Magic bytes every EOF container starts with. (EIP3540)
+pub const EOF_MAGIC: [u8; 2] = [0xef, 0x00];
+
+const EOF_VERSION: u8 = 0x01;
+
+const KIND_TERMINATOR: u8 = 0x00;
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_DATA: u8 = 0x04;
+
+const MAX_CODE_SECTIONS: usize = 1024;
+const MAX_STACK_HEIGHT: usize = 1023;
+
+const OP_RJUMP: u8 = 0xe0;
+const OP_RJUMPI: u8 = 0xe1;
+const OP_RJUMPV: u8 = 0xe2;
+const OP_CALLF: u8 = 0xe3;
+const OP_RETF: u8 = 0xe4;
+
+/// Whether the code is an EOF container, judging by its magic.
+pub fn is_eof(code: &[u8]) -> bool {
+    code.starts_with(&EOF_MAGIC)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Signature of an EOF code section. (EIP4750)
+pub struct FunctionType {
+    /// Number of stack items the section takes.
+    pub inputs: u8,
+    /// Number of stack items the section returns.
+    pub outputs: u8,
+    /// Maximum stack height reached within the section.
+    pub max_stack_height: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A parsed EOF container. (EIP3540)
+pub struct EofContainer {
+    /// Types of the code sections.
+    pub types: Vec<FunctionType>,
+    /// Code sections. The first one is the entry point.
+    pub code_sections: Vec<Vec<u8>>,
+    /// Data section.
+    pub data: Vec<u8>,
+}
+
+/// Stack effect and layout of an instruction within EOF code.
+struct InstructionInfo {
+    inputs: usize,
+    outputs: usize,
+    immediate: usize,
+    terminating: bool,
+}
+
+impl InstructionInfo {
+    fn new(inputs: usize, outputs: usize) -> Self {
+        InstructionInfo {
+            inputs,
+            outputs,
+            immediate: 0,
+            terminating: false,
+        }
+    }
+
+    fn terminating(inputs: usize) -> Self {
+        InstructionInfo {
+            inputs,
+            outputs: 0,
+            immediate: 0,
+            terminating: true,
+        }
+    }
+
+    fn immediate(mut self, immediate: usize) -> Self {
+        self.immediate = immediate;
+        self
+    }
+}
+
+/// Get the instruction info of an opcode, or None if it is undefined
+/// or deprecated in EOF code. JUMP, JUMPI and PC are replaced by
+/// relative jumps, and CALLCODE and SELFDESTRUCT are
+/// removed. (EIP3670, EIP4750)
+fn instruction_info(opcode: u8) -> Option<InstructionInfo> {
+    Some(match opcode {
+        0x00 => InstructionInfo::terminating(0),
+        0x01..=0x07 | 0x0a | 0x0b => InstructionInfo::new(2, 1),
+        0x08 | 0x09 => InstructionInfo::new(3, 1),
+        0x10..=0x14 | 0x16..=0x18 | 0x1a..=0x1d => InstructionInfo::new(2, 1),
+        0x15 | 0x19 => InstructionInfo::new(1, 1),
+        0x20 => InstructionInfo::new(2, 1),
+        0x30 | 0x32 | 0x33 | 0x34 | 0x36 | 0x38 | 0x3a | 0x3d => InstructionInfo::new(0, 1),
+        0x31 | 0x35 | 0x3b | 0x3f => InstructionInfo::new(1, 1),
+        0x37 | 0x39 | 0x3e => InstructionInfo::new(3, 0),
+        0x3c => InstructionInfo::new(4, 0),
+        0x40 | 0x49 => InstructionInfo::new(1, 1),
+        0x41..=0x48 | 0x4a => InstructionInfo::new(0, 1),
+        0x50 => InstructionInfo::new(1, 0),
+        0x51 | 0x54 | 0x5c => InstructionInfo::new(1, 1),
+        0x52 | 0x53 | 0x55 | 0x5d => InstructionInfo::new(2, 0),
+        0x59 | 0x5a | 0x5f => InstructionInfo::new(0, 1),
+        0x5b => InstructionInfo::new(0, 0),
+        0x5e => InstructionInfo::new(3, 0),
+        0x60..=0x7f => InstructionInfo::new(0, 1).immediate((opcode - 0x5f) as usize),
+        0x80..=0x8f => {
+            let n = (opcode - 0x7f) as usize;
+            InstructionInfo::new(n, n + 1)
+        }
+        0x90..=0x9f => {
+            let n = (opcode - 0x8f) as usize;
+            InstructionInfo::new(n + 1, n + 1)
+        }
+        0xa0..=0xa4 => InstructionInfo::new((opcode - 0xa0) as usize + 2, 0),
+        OP_RJUMP => InstructionInfo::terminating(0).immediate(2),
+        OP_RJUMPI => InstructionInfo::new(1, 0).immediate(2),
+        // The immediate size of RJUMPV depends on its jump table and
+        // is computed separately.
+        OP_RJUMPV => InstructionInfo::new(1, 0).immediate(1),
+        // The stack effect of CALLF depends on the called section.
+        OP_CALLF => InstructionInfo::new(0, 0).immediate(2),
+        OP_RETF => InstructionInfo::terminating(0),
+        0xf0 => InstructionInfo::new(3, 1),
+        0xf1 => InstructionInfo::new(7, 1),
+        0xf3 | 0xfd => InstructionInfo::terminating(2),
+        0xf4 | 0xfa => InstructionInfo::new(6, 1),
+        0xf5 => InstructionInfo::new(4, 1),
+        0xfe => InstructionInfo::terminating(0),
+        _ => return None,
+    })
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    if pos + 2 > bytes.len() {
+        None
+    } else {
+        Some(u16::from(bytes[pos]) << 8 | u16::from(bytes[pos + 1]))
+    }
+}
+
+fn read_i16(bytes: &[u8], pos: usize) -> i16 {
+    read_u16(bytes, pos).unwrap() as i16
+}
+
+/// Size of the immediate of the instruction at `pos`, which must have
+/// been checked to be within the code for RJUMPV's table size.
+fn immediate_size(code: &[u8], pos: usize, info: &InstructionInfo) -> usize {
+    if code[pos] == OP_RJUMPV {
+        1 + (code[pos + 1] as usize + 1) * 2
+    } else {
+        info.immediate
+    }
+}
+
+/// Jump targets of a relative jump instruction at `pos`, as offsets
+/// from the start of the code section.
+fn jump_targets(code: &[u8], pos: usize) -> Vec<isize> {
+    match code[pos] {
+        OP_RJUMP | OP_RJUMPI => {
+            let next = (pos + 3) as isize;
+            vec![next + read_i16(code, pos + 1) as isize]
+        }
+        OP_RJUMPV => {
+            let count = code[pos + 1] as usize + 1;
+            let next = (pos + 2 + count * 2) as isize;
+            (0..count)
+                .map(|i| next + read_i16(code, pos + 2 + i * 2) as isize)
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+impl EofContainer {
+    /// Parse an EOF container from its header, type, code and data
+    /// sections. The body must exactly match the sizes declared in the
+    /// header. (EIP3540)
+    pub fn parse(code: &[u8]) -> Result<Self, EofError> {
+        if code.len() < 3 || !is_eof(code) || code[2] != EOF_VERSION {
+            return Err(EofError::InvalidMagic);
+        }
+
+        let mut pos = 3;
+        let expect_kind = |pos: &mut usize, kind: u8| -> Result<(), EofError> {
+            if code.get(*pos) != Some(&kind) {
+                return Err(EofError::InvalidHeader);
+            }
+            *pos += 1;
+            Ok(())
+        };
+        let read_size = |pos: &mut usize| -> Result<usize, EofError> {
+            let size = read_u16(code, *pos).ok_or(EofError::InvalidHeader)?;
+            *pos += 2;
+            Ok(size as usize)
+        };
+
+        expect_kind(&mut pos, KIND_TYPES)?;
+        let types_size = read_size(&mut pos)?;
+
+        expect_kind(&mut pos, KIND_CODE)?;
+        let num_code_sections = read_size(&mut pos)?;
+        if num_code_sections == 0 || num_code_sections > MAX_CODE_SECTIONS {
+            return Err(EofError::InvalidHeader);
+        }
+        let mut code_sizes = Vec::with_capacity(num_code_sections);
+        for _ in 0..num_code_sections {
+            let size = read_size(&mut pos)?;
+            if size == 0 {
+                return Err(EofError::InvalidHeader);
+            }
+            code_sizes.push(size);
+        }
+
+        expect_kind(&mut pos, KIND_DATA)?;
+        let data_size = read_size(&mut pos)?;
+        expect_kind(&mut pos, KIND_TERMINATOR)?;
+
+        if types_size != num_code_sections * 4 {
+            return Err(EofError::InvalidTypes);
+        }
+        let body_size = types_size + code_sizes.iter().sum::<usize>() + data_size;
+        if code.len() - pos != body_size {
+            return Err(EofError::InvalidSize);
+        }
+
+        let mut types = Vec::with_capacity(num_code_sections);
+        for _ in 0..num_code_sections {
+            let ty = FunctionType {
+                inputs: code[pos],
+                outputs: code[pos + 1],
+                max_stack_height: read_u16(code, pos + 2).unwrap(),
+            };
+            if ty.inputs as usize > MAX_STACK_HEIGHT
+                || ty.outputs as usize > MAX_STACK_HEIGHT
+                || ty.max_stack_height as usize > MAX_STACK_HEIGHT
+            {
+                return Err(EofError::InvalidTypes);
+            }
+            types.push(ty);
+            pos += 4;
+        }
+        if types[0].inputs != 0 || types[0].outputs != 0 {
+            return Err(EofError::InvalidTypes);
+        }
+
+        let mut code_sections = Vec::with_capacity(num_code_sections);
+        for size in code_sizes {
+            code_sections.push(code[pos..(pos + size)].to_vec());
+            pos += size;
+        }
+        let data = code[pos..].to_vec();
+
+        Ok(EofContainer {
+            types,
+            code_sections,
+            data,
+        })
+    }
+
+    /// Offsets of the code sections within the encoded container, past
+    /// its header and type section.
+    pub fn code_offsets(&self) -> Vec<usize> {
+        let count = self.code_sections.len();
+        let mut offset = 13 + count * 2 + count * 4;
+        let mut offsets = Vec::with_capacity(count);
+        for section in &self.code_sections {
+            offsets.push(offset);
+            offset += section.len();
+        }
+        offsets
+    }
+
+    /// Validate the instructions, relative jumps, function calls and
+    /// stack heights of every code section.
+    pub fn validate(&self) -> Result<(), EofError> {
+        for index in 0..self.code_sections.len() {
+            let starts = self.validate_code(index)?;
+            self.validate_stack(index, &starts)?;
+        }
+        Ok(())
+    }
+
+    /// Check that a code section only contains defined instructions
+    /// with complete immediates, and that its relative jumps and calls
+    /// have valid targets. Returns which positions start an
+    /// instruction. (EIP3670, EIP4200, EIP4750)
+    fn validate_code(&self, index: usize) -> Result<Vec<bool>, EofError> {
+        let code = &self.code_sections[index];
+        let mut starts = vec![false; code.len()];
+
+        let mut pos = 0;
+        while pos < code.len() {
+            let info = instruction_info(code[pos]).ok_or(EofError::InvalidOpcode(pos))?;
+            // The fixed part of the immediate must be present before
+            // the size of an RJUMPV jump table can be read.
+            if info.immediate > 0 && pos + info.immediate >= code.len() {
+                return Err(EofError::TruncatedImmediate(pos));
+            }
+            let immediate = immediate_size(code, pos, &info);
+            if immediate > 0 && pos + immediate >= code.len() {
+                return Err(EofError::TruncatedImmediate(pos));
+            }
+            if code[pos] == OP_CALLF && read_u16(code, pos + 1).unwrap() as usize >= self.code_sections.len() {
+                return Err(EofError::InvalidCodeSection(pos));
+            }
+
+            starts[pos] = true;
+            pos += 1 + immediate;
+        }
+
+        for (pos, _) in starts.iter().enumerate().filter(|&(_, start)| *start) {
+            for target in jump_targets(code, pos) {
+                if target < 0 || target as usize >= code.len() || !starts[target as usize] {
+                    return Err(EofError::InvalidJumpDestination(pos));
+                }
+            }
+        }
+
+        Ok(starts)
+    }
+
+    /// Check that every instruction of a code section is reachable and
+    /// always sees the same stack height, that the stack never
+    /// underflows or overflows, that RETF returns the declared outputs
+    /// and that the declared maximum stack height is exact. (EIP5450)
+    fn validate_stack(&self, index: usize, starts: &[bool]) -> Result<(), EofError> {
+        let code = &self.code_sections[index];
+        let ty = self.types[index];
+
+        let mut heights: Vec<Option<usize>> = vec![None; code.len()];
+        heights[0] = Some(ty.inputs as usize);
+        let mut worklist = vec![0];
+        let mut max_height = ty.inputs as usize;
+
+        while let Some(pos) = worklist.pop() {
+            let height = heights[pos].unwrap();
+            let info = instruction_info(code[pos]).unwrap();
+
+            let (inputs, outputs) = if code[pos] == OP_CALLF {
+                let callee = self.types[read_u16(code, pos + 1).unwrap() as usize];
+                (callee.inputs as usize, callee.outputs as usize)
+            } else {
+                (info.inputs, info.outputs)
+            };
+            if height < inputs {
+                return Err(EofError::StackUnderflow(pos));
+            }
+            if code[pos] == OP_RETF && height != ty.outputs as usize {
+                return Err(EofError::InconsistentStackHeight(pos));
+            }
+            let new_height = height - inputs + outputs;
+            if new_height > MAX_STACK_HEIGHT {
+                return Err(EofError::StackOverflow(pos));
+            }
+            if new_height > max_height {
+                max_height = new_height;
+            }
+
+            let mut successors: Vec<usize> = jump_targets(code, pos).into_iter().map(|t| t as usize).collect();
+            if !info.terminating {
+                let next = pos + 1 + immediate_size(code, pos, &info);
+                if next >= code.len() {
+                    return Err(EofError::MissingTerminator(pos));
+                }
+                successors.push(next);
+            }
+
+            for successor in successors {
+                match heights[successor] {
+                    None => {
+                        heights[successor] = Some(new_height);
+                        worklist.push(successor);
+                    }
+                    Some(existing) if existing != new_height => {
+                        return Err(EofError::InconsistentStackHeight(successor));
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+
+        if let Some(pos) = (0..code.len()).find(|&pos| starts[pos] && heights[pos].is_none()) {
+            return Err(EofError::UnreachableCode(pos));
+        }
+        if max_height != ty.max_stack_height as usize {
+            return Err(EofError::InvalidMaxStackHeight);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(types: &[(u8, u8, u16)], sections: &[&[u8]], data: &[u8]) -> Vec<u8> {
+        let mut code = vec![0xef, 0x00, 0x01, KIND_TYPES];
+        code.extend_from_slice(&((types.len() * 4) as u16).to_be_bytes());
+        code.push(KIND_CODE);
+        code.extend_from_slice(&(sections.len() as u16).to_be_bytes());
+        for section in sections {
+            code.extend_from_slice(&(section.len() as u16).to_be_bytes());
+        }
+        code.push(KIND_DATA);
+        code.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        code.push(KIND_TERMINATOR);
+        for &(inputs, outputs, max_stack_height) in types {
+            code.push(inputs);
+            code.push(outputs);
+            code.extend_from_slice(&max_stack_height.to_be_bytes());
+        }
+        for section in sections {
+            code.extend_from_slice(section);
+        }
+        code.extend_from_slice(data);
+        code
+    }
+
+    fn validate(code: &[u8]) -> Result<(), EofError> {
+        EofContainer::parse(code)?.validate()
+    }
+
+    #[test]
+    fn valid_containers() {
+        // PUSH1 1, PUSH1 0, MSTORE, STOP
+        assert_eq!(
+            validate(&container(
+                &[(0, 0, 2)],
+                &[&[0x60, 0x01, 0x60, 0x00, 0x52, 0x00]],
+                &[0xaa]
+            )),
+            Ok(())
+        );
+        // PUSH0, RJUMPI +1, STOP, CALLF 1, STOP; section 1: PUSH0, POP, RETF
+        assert_eq!(
+            validate(&container(
+                &[(0, 0, 1), (0, 0, 1)],
+                &[
+                    &[0x5f, 0xe1, 0x00, 0x01, 0x00, 0xe3, 0x00, 0x01, 0x00],
+                    &[0x5f, 0x50, 0xe4]
+                ],
+                &[],
+            )),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn invalid_containers() {
+        assert_eq!(validate(&[0xef, 0x00, 0x02]), Err(EofError::InvalidMagic));
+        assert_eq!(
+            validate(&container(&[(0, 0, 0)], &[&[0x00]], &[])[..19]),
+            Err(EofError::InvalidSize)
+        );
+        // JUMP is not allowed
+        assert_eq!(
+            validate(&container(&[(0, 0, 1)], &[&[0x5f, 0x56]], &[])),
+            Err(EofError::InvalidOpcode(1))
+        );
+        // PUSH2 with one byte of immediate
+        assert_eq!(
+            validate(&container(&[(0, 0, 1)], &[&[0x61, 0x00]], &[])),
+            Err(EofError::TruncatedImmediate(0))
+        );
+        // RJUMP into the immediate of PUSH1
+        assert_eq!(
+            validate(&container(&[(0, 0, 1)], &[&[0x60, 0x00, 0xe0, 0xff, 0xfc]], &[])),
+            Err(EofError::InvalidJumpDestination(2))
+        );
+        // POP on an empty stack
+        assert_eq!(
+            validate(&container(&[(0, 0, 0)], &[&[0x50, 0x00]], &[])),
+            Err(EofError::StackUnderflow(0))
+        );
+        // Falls off the end of the code
+        assert_eq!(
+            validate(&container(&[(0, 0, 1)], &[&[0x5f]], &[])),
+            Err(EofError::MissingTerminator(0))
+        );
+        // Wrong declared max stack height
+        assert_eq!(
+            validate(&container(&[(0, 0, 2)], &[&[0x5f, 0x00]], &[])),
+            Err(EofError::InvalidMaxStackHeight)
+        );
+        // STOP after RJUMP is unreachable
+        assert_eq!(
+            validate(&container(&[(0, 0, 0)], &[&[0xe0, 0x00, 0x00, 0x00, 0x00]], &[])),
+            Err(EofError::UnreachableCode(4))
+        );
+    }
+}
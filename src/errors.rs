@@ -13,6 +13,19 @@ pub enum PreExecutionError {
     InsufficientBalance,
     /// Gas limit is smaller than the intrinsic gas required.
     InsufficientGasLimit,
+    /// Max fee per gas is smaller than the block base fee or the max
+    /// priority fee per gas, or max fee per blob gas is smaller than
+    /// the block blob base fee.
+    InvalidFeeCap,
+    /// The blob fields of the transaction are malformed.
+    InvalidBlobs,
+    /// Init code of a contract creation exceeds the size limit.
+    InitcodeSizeExceeded,
+    /// The transaction type, or its access list, is not supported by
+    /// the patch.
+    UnsupportedTransactionType,
+    /// The transaction is signed for another chain.
+    InvalidChainId,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -41,6 +54,12 @@ pub enum OnChainError {
     NotStatic,
     /// Invoked by REVERT opcode.
     Revert,
+    /// The code returned by a contract creation is rejected by the
+    /// deposit validation rules of the patch.
+    InvalidCode,
+    /// A precompiled contract rejected its input. The call fails,
+    /// consuming all gas given to it.
+    PrecompiledFailure,
 }
 
 impl From<OnChainError> for RuntimeError {
@@ -84,6 +103,52 @@ impl From<NotSupportedError> for EvalError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// Errors when parsing or validating an EOF container.
+pub enum EofError {
+    /// The code does not start with the EOF magic and version.
+    InvalidMagic,
+    /// The section headers are malformed.
+    InvalidHeader,
+    /// The type section is malformed or does not match the code
+    /// sections.
+    InvalidTypes,
+    /// The container body does not match the sizes in the header.
+    InvalidSize,
+    /// Undefined or deprecated opcode at the given position. (EIP3670)
+    InvalidOpcode(usize),
+    /// Immediate of the instruction at the given position is cut off
+    /// by the end of the code section. (EIP3670)
+    TruncatedImmediate(usize),
+    /// Relative jump at the given position targets outside of the code
+    /// section or an immediate. (EIP4200)
+    InvalidJumpDestination(usize),
+    /// CALLF at the given position targets a nonexistent code
+    /// section. (EIP4750)
+    InvalidCodeSection(usize),
+    /// Execution can run past the end of the code section from the
+    /// given position.
+    MissingTerminator(usize),
+    /// Instruction at the given position underflows the stack. (EIP5450)
+    StackUnderflow(usize),
+    /// Instruction at the given position overflows the stack. (EIP5450)
+    StackOverflow(usize),
+    /// Instruction at the given position is reached with different
+    /// stack heights, or RETF returns a wrong number of items. (EIP5450)
+    InconsistentStackHeight(usize),
+    /// Instruction at the given position is never reached. (EIP5450)
+    UnreachableCode(usize),
+    /// The declared maximum stack height of the code section is
+    /// wrong. (EIP5450)
+    InvalidMaxStackHeight,
+}
+
+impl From<EofError> for OnChainError {
+    fn from(_: EofError) -> OnChainError {
+        OnChainError::InvalidCode
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Runtime error. Can either be an on-chain error or a not-supported
 /// error.
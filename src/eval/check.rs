@@ -5,11 +5,33 @@ use bigint::{Gas, M256, U256};
 use crate::{
     errors::{EvalOnChainError, NotSupportedError, OnChainError},
     eval::{ControlCheck, Runtime, State},
-    Instruction, Memory, Patch,
+    is_eof, EofContainer, Instruction, Memory, Patch,
 };
 
 use super::util::check_range;
 
+/// Minimum gas left required for SSTORE under EIP2200.
+const G_SSTORE_SENTRY: usize = 2300;
+
+/// Validate the code returned by a contract creation before it is
+/// deposited. EOF initcode may only deploy EOF code. (EIP3540)
+pub fn check_code_deposit<P: Patch>(patch: &P, initcode: &[u8], code: &[u8]) -> Result<(), OnChainError> {
+    if patch.has_eof() && is_eof(code) {
+        EofContainer::parse(code)?.validate()?;
+        return Ok(());
+    }
+
+    if patch.has_eof() && is_eof(initcode) {
+        return Err(OnChainError::InvalidCode);
+    }
+
+    if patch.has_ef_code_rejection() && code.first() == Some(&0xef) {
+        return Err(OnChainError::InvalidCode);
+    }
+
+    Ok(())
+}
+
 #[allow(unused_variables)]
 pub fn extra_check_opcode<M: Memory, P: Patch>(
     instruction: Instruction,
@@ -42,6 +64,12 @@ pub fn check_support<M: Memory, P: Patch>(
             state.memory.check_write(state.stack.peek(0).unwrap().into())?;
             Ok(())
         }
+        Instruction::MCOPY => {
+            state
+                .memory
+                .check_write_range(state.stack.peek(0).unwrap().into(), state.stack.peek(2).unwrap().into())?;
+            Ok(())
+        }
         Instruction::CALLDATACOPY => {
             state
                 .memory
@@ -141,12 +169,21 @@ pub fn check_static<M: Memory, P: Patch>(
         | Instruction::TIMESTAMP
         | Instruction::NUMBER
         | Instruction::DIFFICULTY
-        | Instruction::GASLIMIT => Ok(()),
+        | Instruction::GASLIMIT
+        | Instruction::CHAINID
+        | Instruction::SELFBALANCE
+        | Instruction::BASEFEE
+        | Instruction::BLOBHASH
+        | Instruction::BLOBBASEFEE => Ok(()),
 
-        Instruction::POP | Instruction::MLOAD | Instruction::MSTORE | Instruction::MSTORE8 => Ok(()),
+        Instruction::POP | Instruction::MLOAD | Instruction::MSTORE | Instruction::MSTORE8 | Instruction::MCOPY => {
+            Ok(())
+        }
 
         Instruction::SLOAD => Ok(()),
         Instruction::SSTORE => Err(EvalOnChainError::OnChain(OnChainError::NotStatic)),
+        Instruction::TLOAD => Ok(()),
+        Instruction::TSTORE => Err(EvalOnChainError::OnChain(OnChainError::NotStatic)),
 
         Instruction::JUMP
         | Instruction::JUMPI
@@ -155,7 +192,13 @@ pub fn check_static<M: Memory, P: Patch>(
         | Instruction::GAS
         | Instruction::JUMPDEST => Ok(()),
 
-        Instruction::PUSH(_) | Instruction::DUP(_) | Instruction::SWAP(_) => Ok(()),
+        Instruction::RJUMP(_)
+        | Instruction::RJUMPI(_)
+        | Instruction::RJUMPV(_)
+        | Instruction::CALLF(_)
+        | Instruction::RETF => Ok(()),
+
+        Instruction::PUSH0 | Instruction::PUSH(_) | Instruction::DUP(_) | Instruction::SWAP(_) => Ok(()),
 
         Instruction::LOG(_) => Err(EvalOnChainError::OnChain(OnChainError::NotStatic)),
         Instruction::CREATE => Err(EvalOnChainError::OnChain(OnChainError::NotStatic)),
@@ -405,6 +448,27 @@ pub fn check_opcode<M: Memory, P: Patch>(
             state.stack.check_pop_push(0, 1)?;
             Ok(None)
         }
+        Instruction::CHAINID => {
+            state.stack.check_pop_push(0, 1)?;
+            Ok(None)
+        }
+        Instruction::SELFBALANCE => {
+            state.stack.check_pop_push(0, 1)?;
+            state.account_state.require(state.context.address)?;
+            Ok(None)
+        }
+        Instruction::BASEFEE => {
+            state.stack.check_pop_push(0, 1)?;
+            Ok(None)
+        }
+        Instruction::BLOBHASH => {
+            state.stack.check_pop_push(1, 1)?;
+            Ok(None)
+        }
+        Instruction::BLOBBASEFEE => {
+            state.stack.check_pop_push(0, 1)?;
+            Ok(None)
+        }
 
         Instruction::POP => {
             state.stack.check_pop_push(1, 0)?;
@@ -432,6 +496,9 @@ pub fn check_opcode<M: Memory, P: Patch>(
         }
         Instruction::SSTORE => {
             state.stack.check_pop_push(2, 0)?;
+            if state.patch.has_net_sstore_gas_metering() && state.available_gas() <= G_SSTORE_SENTRY.into() {
+                return Err(OnChainError::EmptyGas.into());
+            }
             state.account_state.require(state.context.address)?;
             state
                 .account_state
@@ -463,7 +530,39 @@ pub fn check_opcode<M: Memory, P: Patch>(
             Ok(None)
         }
         Instruction::JUMPDEST => Ok(None),
+        Instruction::RJUMP(_) | Instruction::RETF => Ok(None),
+        Instruction::RJUMPI(_) | Instruction::RJUMPV(_) => {
+            state.stack.check_pop_push(1, 0)?;
+            Ok(None)
+        }
+        Instruction::CALLF(index) => {
+            let (_, ty) = state.valids.code_section(index).unwrap();
+            if state.return_stack.len() >= 1024
+                || state.stack.len() + ty.max_stack_height as usize - ty.inputs as usize > 1024
+            {
+                return Err(OnChainError::StackOverflow.into());
+            }
+            Ok(None)
+        }
+        Instruction::TLOAD => {
+            state.stack.check_pop_push(1, 1)?;
+            Ok(None)
+        }
+        Instruction::TSTORE => {
+            state.stack.check_pop_push(2, 0)?;
+            Ok(None)
+        }
+        Instruction::MCOPY => {
+            state.stack.check_pop_push(3, 0)?;
+            check_range(state.stack.peek(0).unwrap().into(), state.stack.peek(2).unwrap().into())?;
+            check_range(state.stack.peek(1).unwrap().into(), state.stack.peek(2).unwrap().into())?;
+            Ok(None)
+        }
 
+        Instruction::PUSH0 => {
+            state.stack.check_pop_push(0, 1)?;
+            Ok(None)
+        }
         Instruction::PUSH(v) => {
             state.stack.check_pop_push(0, 1)?;
             Ok(None)
@@ -486,6 +585,11 @@ pub fn check_opcode<M: Memory, P: Patch>(
         Instruction::CREATE | Instruction::CREATE2 => {
             state.stack.check_pop_push(3, 1)?;
             check_range(state.stack.peek(1).unwrap().into(), state.stack.peek(2).unwrap().into())?;
+            if let Some(limit) = state.patch.initcode_limit() {
+                if state.stack.peek(2).unwrap() > M256::from(limit) {
+                    return Err(OnChainError::EmptyGas.into());
+                }
+            }
             state.account_state.require(state.context.address)?;
             Ok(None)
         }
@@ -17,14 +17,10 @@ const G_LOW: usize = 5;
 const G_MID: usize = 8;
 const G_HIGH: usize = 10;
 const G_JUMPDEST: usize = 1;
+const G_RJUMPI: usize = 4;
 const G_SNOOP: usize = 200;
 const G_SSET: usize = 20000;
 const G_SRESET: usize = 5000;
-const R_SRESET: isize = 15000;
-const R_NETSRESETCLEAR: isize = 19800;
-const R_NETSRESET: isize = 4800;
-const R_NETSCLEAR: isize = 15000;
-const R_SUICIDE: isize = 24000;
 const G_CREATE: usize = 32000;
 const G_CODEDEPOSIT: usize = 200;
 const G_CALLVALUE: usize = 9000;
@@ -39,7 +35,6 @@ const G_SHA3: usize = 30;
 const G_SHA3WORD: usize = 6;
 const G_COPY: usize = 3;
 const G_BLOCKHASH: usize = 20;
-const G_EXTCODEHASH: usize = 400;
 
 fn sstore_cost<M: Memory, P: Patch>(state: &State<M, P>) -> Gas {
     let index: U256 = state.stack.peek(0).unwrap().into();
@@ -48,7 +43,7 @@ fn sstore_cost<M: Memory, P: Patch>(state: &State<M, P>) -> Gas {
     let current = state.account_state.storage_read(address, index).unwrap();
 
     // The legacy gas metering only takes into consideration the current state
-    if !state.patch.has_reduced_sstore_gas_metering() {
+    if !state.patch.has_reduced_sstore_gas_metering() && !state.patch.has_net_sstore_gas_metering() {
         if current == M256::zero() && value != M256::zero() {
             return G_SSET.into();
         } else {
@@ -56,11 +51,12 @@ fn sstore_cost<M: Memory, P: Patch>(state: &State<M, P>) -> Gas {
         }
     }
 
-    // Modern gas metering scheme (EIP-1283)
-    trace!("using EIP1283 reduced SSTORE gas metering scheme");
+    // Modern gas metering scheme (EIP-1283 or EIP-2200)
+    trace!("using net SSTORE gas metering scheme");
+    let snoop = sstore_snoop_cost(state);
 
     if value == current {
-        return G_SNOOP.into();
+        return snoop;
     }
 
     // If RequireError is thrown here, that means that original storage was unset, hence defaulting to Zero.
@@ -73,16 +69,74 @@ fn sstore_cost<M: Memory, P: Patch>(state: &State<M, P>) -> Gas {
         if original == M256::zero() {
             G_SSET.into()
         } else {
-            G_SRESET.into()
+            sstore_reset_cost(state)
         }
+    } else {
+        snoop
+    }
+}
+
+/// Cost of an SSTORE that does not change the original value. EIP2200 prices it as an SLOAD,
+/// and EIP2929 as a warm storage access.
+fn sstore_snoop_cost<M: Memory, P: Patch>(state: &State<M, P>) -> Gas {
+    if state.patch.has_warm_cold_access() {
+        state.patch.gas_warm_access()
+    } else if state.patch.has_net_sstore_gas_metering() {
+        state.patch.gas_sload()
     } else {
         G_SNOOP.into()
     }
 }
 
+/// Cost of an SSTORE resetting a non-zero original value. EIP2929 moves the cold part of it
+/// into the cold storage access cost.
+fn sstore_reset_cost<M: Memory, P: Patch>(state: &State<M, P>) -> Gas {
+    if state.patch.has_warm_cold_access() {
+        Gas::from(G_SRESET) - state.patch.gas_cold_sload()
+    } else {
+        G_SRESET.into()
+    }
+}
+
+/// Extra cost of an SSTORE to a storage slot not yet accessed in this transaction. (EIP2929)
+fn sstore_cold_cost<M: Memory, P: Patch>(state: &State<M, P>) -> Gas {
+    let index: U256 = state.stack.peek(0).unwrap().into();
+    if state.patch.has_warm_cold_access() && !state.account_state.is_storage_accessed(state.context.address, index) {
+        state.patch.gas_cold_sload()
+    } else {
+        Gas::zero()
+    }
+}
+
+fn sload_cost<M: Memory, P: Patch>(state: &State<M, P>) -> Gas {
+    let index: U256 = state.stack.peek(0).unwrap().into();
+    if !state.patch.has_warm_cold_access() {
+        state.patch.gas_sload()
+    } else if state.account_state.is_storage_accessed(state.context.address, index) {
+        state.patch.gas_warm_access()
+    } else {
+        state.patch.gas_cold_sload()
+    }
+}
+
+/// Cost of an instruction touching the given account. EIP2929 replaces the patch-defined cost
+/// with the warm or cold account access cost.
+fn account_access_cost<M: Memory, P: Patch>(state: &State<M, P>, address: Address, cost: Gas) -> Gas {
+    if !state.patch.has_warm_cold_access() {
+        cost
+    } else if state.account_state.is_address_accessed(address) {
+        state.patch.gas_warm_access()
+    } else {
+        state.patch.gas_cold_account_access()
+    }
+}
+
 fn call_cost<M: Memory, P: Patch>(machine: &State<M, P>, instruction: &Instruction) -> Gas {
     let transfers_value = machine.stack.peek(2).unwrap() != M256::zero();
-    machine.patch.gas_call() + xfer_cost(instruction, transfers_value) + new_cost(machine, instruction, transfers_value)
+    let address: Address = machine.stack.peek(1).unwrap().into();
+    account_access_cost(machine, address, machine.patch.gas_call())
+        + xfer_cost(instruction, transfers_value)
+        + new_cost(machine, instruction, transfers_value)
 }
 
 fn xfer_cost(instruction: &Instruction, transfers_value: bool) -> Gas {
@@ -147,7 +201,15 @@ fn suicide_cost<M: Memory, P: Patch>(machine: &State<M, P>) -> Gas {
         Gas::zero()
     };
 
-    machine.patch.gas_suicide() + suicide_gas_topup
+    // EIP2929 only charges for a cold target, there is no warm access cost
+    let cold_access_cost =
+        if machine.patch.has_warm_cold_access() && !machine.account_state.is_address_accessed(target_address) {
+            machine.patch.gas_cold_account_access()
+        } else {
+            Gas::zero()
+        };
+
+    machine.patch.gas_suicide() + suicide_gas_topup + cold_access_cost
 }
 
 fn memory_expand(current: Gas, from: Gas, len: Gas) -> Gas {
@@ -164,6 +226,27 @@ fn memory_expand(current: Gas, from: Gas, len: Gas) -> Gas {
     max(current, new)
 }
 
+/// Number of 32-byte words needed to hold `len` bytes.
+fn word_count(len: U256) -> Gas {
+    let len = Gas::from(len);
+    let rem = len % Gas::from(32u64);
+    if rem == Gas::zero() {
+        len / Gas::from(32u64)
+    } else {
+        len / Gas::from(32u64) + Gas::from(1u64)
+    }
+}
+
+/// Calculate the per-word cost of init code for CREATE and CREATE2,
+/// charged only when the init code size is limited. (EIP3860)
+fn initcode_cost<M: Memory, P: Patch>(state: &State<M, P>, init_code_len: U256) -> Gas {
+    if state.patch.initcode_limit().is_some() {
+        state.patch.gas_initcode_word() * word_count(init_code_len)
+    } else {
+        Gas::zero()
+    }
+}
+
 /// Calculate code deposit cost for a ContractCreation transaction.
 pub fn code_deposit_gas(len: usize) -> Gas {
     Gas::from(G_CODEDEPOSIT) * Gas::from(len)
@@ -204,6 +287,14 @@ pub fn memory_cost<M: Memory, P: Patch>(instruction: Instruction, state: &State<
             let from: U256 = stack.peek(0).unwrap().into();
             memory_expand(current, Gas::from(from), Gas::from(1u64))
         }
+        Instruction::MCOPY => {
+            let dst: U256 = stack.peek(0).unwrap().into();
+            let src: U256 = stack.peek(1).unwrap().into();
+            let len: U256 = stack.peek(2).unwrap().into();
+            let dst_cost = memory_expand(current, Gas::from(dst), Gas::from(len));
+            let src_cost = memory_expand(current, Gas::from(src), Gas::from(len));
+            max(dst_cost, src_cost)
+        }
         Instruction::CREATE | Instruction::CREATE2 => {
             let from: U256 = stack.peek(1).unwrap().into();
             let len: U256 = stack.peek(2).unwrap().into();
@@ -232,7 +323,7 @@ pub fn gas_cost<M: Memory, P: Patch>(instruction: Instruction, state: &State<M,
         Instruction::DELEGATECALL => call_cost::<M, P>(state, &Instruction::DELEGATECALL),
         Instruction::STATICCALL => call_cost::<M, P>(state, &Instruction::STATICCALL),
         Instruction::SUICIDE => suicide_cost::<M, P>(state),
-        Instruction::SSTORE => sstore_cost(state),
+        Instruction::SSTORE => sstore_cost(state) + sstore_cold_cost(state),
 
         Instruction::SHA3 => {
             let len = state.stack.peek(1).unwrap();
@@ -256,7 +347,8 @@ pub fn gas_cost<M: Memory, P: Patch>(instruction: Instruction, state: &State<M,
             let len = state.stack.peek(3).unwrap();
             let wordd = Gas::from(len) / Gas::from(32u64);
             let wordr = Gas::from(len) % Gas::from(32u64);
-            state.patch.gas_extcode()
+            let address: Address = state.stack.peek(0).unwrap().into();
+            account_access_cost(state, address, state.patch.gas_extcode())
                 + Gas::from(G_COPY)
                     * if wordr == Gas::zero() {
                         wordd
@@ -265,7 +357,7 @@ pub fn gas_cost<M: Memory, P: Patch>(instruction: Instruction, state: &State<M,
                     }
         }
 
-        Instruction::CALLDATACOPY | Instruction::CODECOPY | Instruction::RETURNDATACOPY => {
+        Instruction::CALLDATACOPY | Instruction::CODECOPY | Instruction::RETURNDATACOPY | Instruction::MCOPY => {
             let len = state.stack.peek(2).unwrap();
             let wordd = Gas::from(len) / Gas::from(32u64);
             let wordr = Gas::from(len) % Gas::from(32u64);
@@ -288,17 +380,19 @@ pub fn gas_cost<M: Memory, P: Patch>(instruction: Instruction, state: &State<M,
             }
         }
 
-        Instruction::CREATE => G_CREATE.into(),
+        Instruction::CREATE => {
+            let init_code_len: U256 = state.stack.peek(2).unwrap().into();
+            Gas::from(G_CREATE) + initcode_cost(state, init_code_len)
+        }
         Instruction::CREATE2 => {
-            let base = G_CREATE;
-            let init_code_len = state.stack.peek(2).unwrap().as_usize();
-            // ceil(init_code_len / 32.0)
-            let sha_addup_base = init_code_len / 32 + if init_code_len % 32 == 0 { 0 } else { 1 };
-            let sha_addup = G_SHA3WORD * sha_addup_base;
-            (base + sha_addup).into()
+            let init_code_len: U256 = state.stack.peek(2).unwrap().into();
+            let sha_addup = Gas::from(G_SHA3WORD) * word_count(init_code_len);
+            Gas::from(G_CREATE) + sha_addup + initcode_cost(state, init_code_len)
         }
         Instruction::JUMPDEST => G_JUMPDEST.into(),
-        Instruction::SLOAD => state.patch.gas_sload(),
+        Instruction::RJUMPI(_) | Instruction::RJUMPV(_) => G_RJUMPI.into(),
+        Instruction::SLOAD => sload_cost(state),
+        Instruction::TLOAD | Instruction::TSTORE => state.patch.gas_warm_access(),
 
         // W_zero
         Instruction::STOP | Instruction::RETURN | Instruction::REVERT => G_ZERO.into(),
@@ -317,10 +411,15 @@ pub fn gas_cost<M: Memory, P: Patch>(instruction: Instruction, state: &State<M,
         | Instruction::NUMBER
         | Instruction::DIFFICULTY
         | Instruction::GASLIMIT
+        | Instruction::CHAINID
+        | Instruction::BASEFEE
+        | Instruction::BLOBBASEFEE
         | Instruction::POP
         | Instruction::PC
         | Instruction::MSIZE
-        | Instruction::GAS => G_BASE.into(),
+        | Instruction::GAS
+        | Instruction::PUSH0
+        | Instruction::RJUMP(_) => G_BASE.into(),
 
         // W_verylow
         Instruction::ADD
@@ -345,7 +444,9 @@ pub fn gas_cost<M: Memory, P: Patch>(instruction: Instruction, state: &State<M,
         | Instruction::SWAP(_)
         | Instruction::SHL
         | Instruction::SHR
-        | Instruction::SAR => G_VERYLOW.into(),
+        | Instruction::SAR
+        | Instruction::BLOBHASH
+        | Instruction::RETF => G_VERYLOW.into(),
 
         // W_low
         Instruction::MUL
@@ -353,7 +454,9 @@ pub fn gas_cost<M: Memory, P: Patch>(instruction: Instruction, state: &State<M,
         | Instruction::SDIV
         | Instruction::MOD
         | Instruction::SMOD
-        | Instruction::SIGNEXTEND => G_LOW.into(),
+        | Instruction::SIGNEXTEND
+        | Instruction::SELFBALANCE
+        | Instruction::CALLF(_) => G_LOW.into(),
 
         // W_mid
         Instruction::ADDMOD | Instruction::MULMOD | Instruction::JUMP => G_MID.into(),
@@ -362,10 +465,18 @@ pub fn gas_cost<M: Memory, P: Patch>(instruction: Instruction, state: &State<M,
         Instruction::JUMPI => G_HIGH.into(),
 
         // W_extcode
-        Instruction::EXTCODESIZE => state.patch.gas_extcode(),
-        Instruction::BALANCE => state.patch.gas_balance(),
+        Instruction::EXTCODESIZE => {
+            account_access_cost(state, state.stack.peek(0).unwrap().into(), state.patch.gas_extcode())
+        }
+        Instruction::BALANCE => {
+            account_access_cost(state, state.stack.peek(0).unwrap().into(), state.patch.gas_balance())
+        }
         Instruction::BLOCKHASH => G_BLOCKHASH.into(),
-        Instruction::EXTCODEHASH => G_EXTCODEHASH.into(),
+        Instruction::EXTCODEHASH => account_access_cost(
+            state,
+            state.stack.peek(0).unwrap().into(),
+            state.patch.gas_extcodehash(),
+        ),
     }
 }
 
@@ -385,6 +496,34 @@ pub fn gas_stipend<M: Memory, P: Patch>(instruction: Instruction, state: &State<
     }
 }
 
+/// Mark the accounts and storage slots charged for by `gas_cost` as
+/// accessed. (EIP2929)
+pub fn mark_accessed<M: Memory, P: Patch>(instruction: Instruction, state: &mut State<M, P>) {
+    if !state.patch.has_warm_cold_access() {
+        return;
+    }
+
+    match instruction {
+        Instruction::SLOAD | Instruction::SSTORE => {
+            let index: U256 = state.stack.peek(0).unwrap().into();
+            state.account_state.access_storage(state.context.address, index);
+        }
+        Instruction::BALANCE
+        | Instruction::EXTCODESIZE
+        | Instruction::EXTCODECOPY
+        | Instruction::EXTCODEHASH
+        | Instruction::SUICIDE => {
+            let address: Address = state.stack.peek(0).unwrap().into();
+            state.account_state.access_address(address);
+        }
+        Instruction::CALL | Instruction::CALLCODE | Instruction::DELEGATECALL | Instruction::STATICCALL => {
+            let address: Address = state.stack.peek(1).unwrap().into();
+            state.account_state.access_address(address);
+        }
+        _ => (),
+    }
+}
+
 /// Calculate the refunded gas.
 pub fn gas_refund<M: Memory, P: Patch>(instruction: Instruction, state: &State<M, P>) -> isize {
     match instruction {
@@ -395,15 +534,16 @@ pub fn gas_refund<M: Memory, P: Patch>(instruction: Instruction, state: &State<M
             let current = state.account_state.storage_read(address, index).unwrap();
 
             // The legacy gas metering only takes into consideration the current state
-            if !state.patch.has_reduced_sstore_gas_metering() {
+            if !state.patch.has_reduced_sstore_gas_metering() && !state.patch.has_net_sstore_gas_metering() {
                 if current != M256::zero() && value == M256::zero() {
-                    return R_SRESET;
+                    return state.patch.refund_sstore_clears().as_usize() as isize;
                 } else {
                     return 0;
                 }
             }
 
-            // Modern gas metering scheme (EIP-1283)
+            // Modern gas metering scheme (EIP-1283 or EIP-2200)
+            let sstore_clears = state.patch.refund_sstore_clears().as_usize() as isize;
             if current == value {
                 return 0;
             }
@@ -418,32 +558,36 @@ pub fn gas_refund<M: Memory, P: Patch>(instruction: Instruction, state: &State<M
             let mut refund = 0;
 
             if original == current && value == M256::zero() {
-                return R_NETSCLEAR;
+                return sstore_clears;
             }
 
             if original != M256::zero() {
                 if current == M256::zero() {
-                    refund -= R_NETSCLEAR;
+                    refund -= sstore_clears;
                 } else if value == M256::zero() {
-                    refund += R_NETSCLEAR;
+                    refund += sstore_clears;
                 }
             }
 
             if original == value {
+                let snoop = sstore_snoop_cost(state).as_usize() as isize;
                 if original == M256::zero() {
-                    refund += R_NETSRESETCLEAR
+                    refund += G_SSET as isize - snoop;
                 } else {
-                    refund += R_NETSRESET
+                    refund += sstore_reset_cost(state).as_usize() as isize - snoop;
                 }
             }
 
             refund
         }
         Instruction::SUICIDE => {
-            if state.removed.contains(&state.context.address) {
+            let address = state.context.address;
+            if state.removed.contains(&address)
+                || (state.patch.has_restricted_suicide() && !state.account_state.is_created(address))
+            {
                 0
             } else {
-                R_SUICIDE
+                state.patch.refund_suicide().as_usize() as isize
             }
         }
         _ => 0,
@@ -8,13 +8,19 @@ use alloc::rc::Rc;
 #[cfg(feature = "std")]
 use std::rc::Rc;
 
+#[cfg(not(feature = "std"))]
+use core::cmp::min;
+#[cfg(feature = "std")]
+use std::cmp::min;
+
+use super::check::check_code_deposit;
 use super::cost::code_deposit_gas;
 use super::util::copy_into_memory_apply;
 use super::{GasUsage, Machine, MachineStatus};
 use crate::{
     commit::AccountState,
     errors::{OnChainError, RequireError},
-    AccountPatch, Memory, Patch,
+    AccessListItem, AccountPatch, Memory, Patch,
 };
 use bigint::{Address, Gas, M256, U256};
 
@@ -115,6 +121,21 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
         Ok(())
     }
 
+    /// Mark the addresses and storage keys in a transaction access list
+    /// as accessed. (EIP2930)
+    pub fn initialize_access_list(&mut self, access_list: &[AccessListItem]) {
+        if !self.state.patch.has_warm_cold_access() {
+            return;
+        }
+
+        for item in access_list {
+            self.state.account_state.access_address(item.address);
+            for index in &item.storage_keys {
+                self.state.account_state.access_storage(item.address, *index);
+            }
+        }
+    }
+
     /// Deposit code for a ContractCreation transaction or a CREATE opcode.
     #[allow(clippy::collapsible_if)]
     pub fn code_deposit(&mut self) {
@@ -130,6 +151,11 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
             }
         }
 
+        if let Err(err) = check_code_deposit(self.state.patch, &self.state.context.code, &self.state.out) {
+            reset_error_hard!(self, err);
+            return;
+        }
+
         let deposit_cost = code_deposit_gas(self.state.out.len());
         if deposit_cost > self.state.available_gas() {
             if !self.state.patch.force_code_deposit() {
@@ -148,15 +174,21 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
     }
 
     /// Finalize a transaction. This should not be used when invoked
-    /// by an opcode and should only be used in the top level.
+    /// by an opcode and should only be used in the top level. If the
+    /// patch has a base fee, the base fee portion of the transaction
+    /// fee is burnt and only the priority fee goes to the beneficiary.
+    /// The blob fee, already included in the preclaimed value, is
+    /// burnt as well.
     ///
     /// ### Panic
     /// Requires caller of the transaction to be committed.
     pub fn finalize_transaction(
         &mut self,
         beneficiary: Address,
+        base_fee: Gas,
         real_used_gas: Gas,
         preclaimed_value: U256,
+        blob_fee: U256,
         fresh_account_state: &AccountState<'a, P::Account>,
     ) -> Result<(), RequireError> {
         self.state.account_state.require(self.state.context.address)?;
@@ -192,9 +224,19 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
             self.state
                 .account_state
                 .decrease_balance(self.state.context.caller, gas_dec.into());
+            self.state
+                .account_state
+                .decrease_balance(self.state.context.caller, blob_fee);
 
             // Apply miner rewards
-            self.state.account_state.increase_balance(beneficiary, gas_dec.into());
+            let gas_price = self.state.context.gas_price;
+            let reward_price = if self.state.patch.has_base_fee() {
+                gas_price - min(base_fee, gas_price)
+            } else {
+                gas_price
+            };
+            let reward = real_used_gas * reward_price;
+            self.state.account_state.increase_balance(beneficiary, reward.into());
         }
 
         for address in &self.state.removed {
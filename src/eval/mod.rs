@@ -16,13 +16,13 @@ use core::ops::AddAssign;
 use std::ops::AddAssign;
 
 use super::commit::{AccountState, BlockhashState};
-use super::errors::{CommitError, EvalOnChainError, NotSupportedError, OnChainError, RequireError, RuntimeError};
+use super::errors::{CommitError, EvalError, EvalOnChainError, NotSupportedError, OnChainError, RequireError};
 use super::pc::Instruction;
 use super::{AccountCommitment, Context, HeaderParams, Log, Memory, Opcode, PCMut, Patch, Stack, Valids, PC};
 use bigint::{Address, Gas, M256, U256};
 
 use self::check::{check_opcode, check_static, check_support, extra_check_opcode};
-use self::cost::{gas_cost, gas_refund, gas_stipend, memory_cost, memory_gas, AddRefund};
+use self::cost::{gas_cost, gas_refund, gas_stipend, mark_accessed, memory_cost, memory_gas, AddRefund};
 use self::run::run_opcode;
 
 macro_rules! reset_error_hard {
@@ -55,6 +55,8 @@ mod check;
 mod cost;
 mod lifecycle;
 mod run;
+#[cfg(test)]
+pub(crate) mod tests;
 mod util;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -108,6 +110,8 @@ pub struct State<'a, M, P: Patch> {
 
     /// Depth of this runtime.
     pub depth: usize,
+    /// Return positions of the EOF functions called by CALLF. (EIP4750)
+    pub return_stack: Vec<usize>,
 
     /// Code valid maps.
     pub valids: Valids,
@@ -206,6 +210,7 @@ pub enum Control {
     Stop,
     Revert,
     Jump(M256),
+    Goto(usize),
     InvokeCreate(Context),
     InvokeCall(Context, (U256, U256)),
 }
@@ -216,6 +221,7 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
     /// review whether it wants to accept the result of this sub
     /// runtime afterwards.
     pub fn derive(&self, context: Context) -> Self {
+        let valids = Valids::with_patch(self.state.patch, context.code.as_slice());
         Machine {
             status: MachineStatus::Running,
             state: State {
@@ -235,29 +241,88 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
                 removed: self.state.removed.clone(),
 
                 depth: self.state.depth + 1,
+                return_stack: Vec::new(),
 
-                position: 0,
-                valids: Valids::new(context.code.as_slice()),
+                position: valids.entry(),
+                valids,
 
                 context,
             },
         }
     }
 
-    /// Create a new runtime.
+    /// Create a new runtime. All precompiled contracts are warmed,
+    /// regardless of their activation block.
+    #[deprecated(note = "use `with_runtime`, which warms the precompiled contracts active at the block")]
     pub fn new(patch: &'a P, context: Context, depth: usize) -> Self {
         let account_patch = patch.account_patch();
-        Self::with_states(patch, context, depth, AccountState::new(account_patch))
+        Self::with_number_states(
+            patch,
+            context,
+            U256::max_value(),
+            depth,
+            AccountState::new(account_patch),
+        )
     }
 
-    /// Create a new runtime with the given states.
+    /// Create a new runtime with the given states. All precompiled
+    /// contracts are warmed, regardless of their activation block.
+    #[deprecated(note = "use `with_runtime_states`, which warms the precompiled contracts active at the block")]
     pub fn with_states(
         patch: &'a P,
         context: Context,
         depth: usize,
         account_state: AccountState<'a, P::Account>,
+    ) -> Self {
+        Self::with_number_states(patch, context, U256::max_value(), depth, account_state)
+    }
+
+    /// Create a new runtime executing in the block of the given
+    /// runtime.
+    pub fn with_runtime(patch: &'a P, context: Context, runtime: &Runtime, depth: usize) -> Self {
+        let account_patch = patch.account_patch();
+        Self::with_runtime_states(patch, context, runtime, depth, AccountState::new(account_patch))
+    }
+
+    /// Create a new runtime executing in the block of the given
+    /// runtime, with the given states.
+    pub fn with_runtime_states(
+        patch: &'a P,
+        context: Context,
+        runtime: &Runtime,
+        depth: usize,
+        account_state: AccountState<'a, P::Account>,
+    ) -> Self {
+        Self::with_number_states(patch, context, runtime.block.number, depth, account_state)
+    }
+
+    fn with_number_states(
+        patch: &'a P,
+        context: Context,
+        number: U256,
+        depth: usize,
+        mut account_state: AccountState<'a, P::Account>,
     ) -> Self {
         let memory_limit = patch.memory_limit();
+
+        // Transient storage and the set of created accounts only
+        // live for the duration of a transaction.
+        account_state.clear_transient_storage();
+        account_state.clear_created();
+
+        // A new top-level runtime starts a new transaction, whose
+        // sender, target and precompiled contracts are always warm.
+        if patch.has_warm_cold_access() {
+            account_state.clear_accessed();
+            account_state.access_address(context.origin);
+            account_state.access_address(context.caller);
+            account_state.access_address(context.address);
+            for address in patch.precompiled_addresses(number) {
+                account_state.access_address(address);
+            }
+        }
+
+        let valids = Valids::with_patch(patch, context.code.as_slice());
         Machine {
             status: MachineStatus::Running,
             state: State {
@@ -277,8 +342,9 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
                 removed: Vec::new(),
 
                 depth,
-                position: 0,
-                valids: Valids::new(context.code.as_slice()),
+                return_stack: Vec::new(),
+                position: valids.entry(),
+                valids,
 
                 context,
             },
@@ -292,22 +358,26 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
 
     /// Step a precompiled runtime. This function returns true if the
     /// runtime is indeed a precompiled address. Otherwise return
-    /// false with state unchanged.
-    pub fn step_precompiled(&mut self) -> bool {
-        for precompiled in self.state.patch.precompileds() {
-            if self.state.context.callee == precompiled.0
-                && self
-                    .state
-                    .patch
-                    .is_precompiled_contract_enabled(&self.state.context.callee)
-                && (precompiled.1.is_none() || precompiled.1.unwrap() == self.state.context.code.as_slice())
-            {
-                let data = &self.state.context.data;
-                match precompiled.2.gas_and_step(data, self.state.context.gas_limit) {
-                    Err(RuntimeError::OnChain(err)) => {
+    /// false with state unchanged. It returns a `RequireError` with
+    /// state unchanged if the precompiled contract needs accounts to
+    /// be committed.
+    pub fn step_precompiled(&mut self, runtime: &Runtime) -> Result<bool, RequireError> {
+        let patch = self.state.patch;
+        let (address, code) = (&self.state.context.callee, &self.state.context.code);
+        match patch.precompiled(address, code, runtime.block.number) {
+            Some(precompiled) => {
+                match precompiled.gas_and_step_with_context(
+                    &self.state.context,
+                    &self.state.account_state,
+                    patch.crypto(),
+                ) {
+                    Err(EvalError::Require(err)) => {
+                        return Err(err);
+                    }
+                    Err(EvalError::OnChain(err)) => {
                         reset_error_hard!(self, err);
                     }
-                    Err(RuntimeError::NotSupported(err)) => {
+                    Err(EvalError::NotSupported(err)) => {
                         reset_error_not_supported!(self, err);
                     }
                     Ok((gas, ret)) => {
@@ -317,10 +387,10 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
                         self.status = MachineStatus::ExitedOk;
                     }
                 }
-                return true;
+                Ok(true)
             }
+            None => Ok(false),
         }
-        false
     }
 
     /// Peek the next instruction.
@@ -375,7 +445,7 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
             _ => panic!(),
         }
 
-        if self.step_precompiled() {
+        if self.step_precompiled(runtime)? {
             trace!("precompiled step succeeded");
             return Ok(());
         }
@@ -507,6 +577,8 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
         .read()
         .unwrap();
 
+        mark_accessed(instruction, &mut self.state);
+
         let result = run_opcode::<M, P>(
             (instruction, position),
             &mut self.state,
@@ -537,6 +609,18 @@ impl<'a, M: Memory, P: Patch> Machine<'a, M, P> {
 
                 Ok(())
             }
+            Some(Control::Goto(position)) => {
+                PCMut::<P>::new(
+                    self.state.patch,
+                    &self.state.context.code,
+                    &self.state.valids,
+                    &mut self.state.position,
+                )
+                .goto(position)
+                .unwrap();
+
+                Ok(())
+            }
             Some(Control::InvokeCall(context, (from, len))) => {
                 self.status = MachineStatus::InvokeCall(context, (from, len));
                 Ok(())
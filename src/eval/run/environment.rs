@@ -1,10 +1,9 @@
 //! Environment instructions
 
 use bigint::{H256, M256};
-use sha3::{Digest, Keccak256};
 
 use super::State;
-use crate::{Address, Memory, Patch};
+use crate::{Address, CryptoProvider, Memory, Patch};
 
 pub fn calldataload<M: Memory, P: Patch>(state: &mut State<M, P>) {
     pop!(state, index);
@@ -27,28 +26,26 @@ pub fn extcodehash<M: Memory, P: Patch>(state: &mut State<M, P>) {
     pop!(state, address: Address);
 
     if let Some(code) = state.account_state.code_opt_nonexist(address).unwrap() {
-        let hash = extcodehash_impl(&code[..]);
+        let hash = extcodehash_impl(&code[..], state.patch.crypto());
         push!(state, hash.into());
     } else {
         push!(state, M256::zero())
     }
 }
 
-fn extcodehash_impl(code: &[u8]) -> H256 {
-    let mut hasher = Keccak256::default();
-    hasher.input(code);
-    let output = hasher.result();
-    H256::from(&output[..])
+fn extcodehash_impl(code: &[u8], crypto: &dyn CryptoProvider) -> H256 {
+    crypto.keccak256(code)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::NATIVE_CRYPTO_PROVIDER;
 
     #[test]
     fn extcodehash_empty_code() {
         let code = &[];
-        let hash = extcodehash_impl(code);
+        let hash = extcodehash_impl(code, &NATIVE_CRYPTO_PROVIDER);
         assert_eq!(
             hash,
             H256::from("0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
@@ -60,7 +57,7 @@ mod tests {
         let code = &[
             0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x63, 0x6f, 0x64, 0x65, 0x68, 0x61, 0x73, 0x68,
         ];
-        let hash = extcodehash_impl(code);
+        let hash = extcodehash_impl(code, &NATIVE_CRYPTO_PROVIDER);
         assert_eq!(
             hash,
             H256::from("0x854a9ae2c913e6cef584ecaf9cbb52a38b59fb923a09beccee9d17c17d15cf7a")
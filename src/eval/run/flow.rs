@@ -18,6 +18,19 @@ pub fn sstore<M: Memory, P: Patch>(state: &mut State<M, P>) {
         .unwrap();
 }
 
+pub fn tload<M: Memory, P: Patch>(state: &mut State<M, P>) {
+    pop!(state, index: U256);
+    let value = state.account_state.transient_storage_read(state.context.address, index);
+    push!(state, value);
+}
+
+pub fn tstore<M: Memory, P: Patch>(state: &mut State<M, P>) {
+    pop!(state, index: U256, value: M256);
+    state
+        .account_state
+        .transient_storage_write(state.context.address, index, value);
+}
+
 pub fn mload<M: Memory, P: Patch>(state: &mut State<M, P>) {
     pop!(state, index: U256);
     let value = state.memory.read(index);
@@ -33,3 +46,8 @@ pub fn mstore8<M: Memory, P: Patch>(state: &mut State<M, P>) {
     pop!(state, index: U256, value: M256);
     state.memory.write_raw(index, (value.0.low_u32() & 0xFF) as u8).unwrap();
 }
+
+pub fn mcopy<M: Memory, P: Patch>(state: &mut State<M, P>) {
+    pop!(state, dst: U256, src: U256, len: U256);
+    state.memory.copy(dst, src, len).unwrap();
+}
@@ -296,13 +296,44 @@ pub fn run_opcode<M: Memory, P: Patch>(
             None
         }
         Instruction::DIFFICULTY => {
-            push!(state, M256::from(runtime.block.difficulty));
+            if state.patch.has_prev_randao() {
+                push!(state, M256::from(runtime.block.prev_randao));
+            } else {
+                push!(state, M256::from(runtime.block.difficulty));
+            }
             None
         }
         Instruction::GASLIMIT => {
             push!(state, runtime.block.gas_limit.into());
             None
         }
+        Instruction::CHAINID => {
+            push!(state, state.patch.chain_id().into());
+            None
+        }
+        Instruction::SELFBALANCE => {
+            let balance = state.account_state.balance(state.context.address).unwrap();
+            push!(state, balance.into());
+            None
+        }
+        Instruction::BASEFEE => {
+            push!(state, runtime.block.base_fee.into());
+            None
+        }
+        Instruction::BLOBHASH => {
+            pop!(state, index: U256);
+            let hash = if index < U256::from(state.context.blob_versioned_hashes.len()) {
+                M256::from(state.context.blob_versioned_hashes[index.as_usize()])
+            } else {
+                M256::zero()
+            };
+            push!(state, hash);
+            None
+        }
+        Instruction::BLOBBASEFEE => {
+            push!(state, runtime.block.blob_base_fee(state.patch).into());
+            None
+        }
 
         Instruction::POP => {
             state.stack.pop().unwrap();
@@ -353,7 +384,52 @@ pub fn run_opcode<M: Memory, P: Patch>(
             None
         }
         Instruction::JUMPDEST => None,
+        Instruction::RJUMP(offset) => Some(Control::Goto(relative_target(pc.1 + 3, offset))),
+        Instruction::RJUMPI(offset) => {
+            pop!(state, value);
+            if value != M256::zero() {
+                Some(Control::Goto(relative_target(pc.1 + 3, offset)))
+            } else {
+                None
+            }
+        }
+        Instruction::RJUMPV(max_index) => {
+            pop!(state, case);
+            let next = pc.1 + 2 + (max_index as usize + 1) * 2;
+            if case <= M256::from(max_index as u64) {
+                let entry = pc.1 + 2 + case.as_usize() * 2;
+                let code = &state.context.code;
+                let offset = (u16::from(code[entry]) << 8 | u16::from(code[entry + 1])) as i16;
+                Some(Control::Goto(relative_target(next, offset)))
+            } else {
+                None
+            }
+        }
+        Instruction::CALLF(index) => {
+            state.return_stack.push(pc.1 + 3);
+            Some(Control::Goto(state.valids.code_section(index).unwrap().0))
+        }
+        Instruction::RETF => match state.return_stack.pop() {
+            Some(position) => Some(Control::Goto(position)),
+            None => Some(Control::Stop),
+        },
+        Instruction::TLOAD => {
+            flow::tload(state);
+            None
+        }
+        Instruction::TSTORE => {
+            flow::tstore(state);
+            None
+        }
+        Instruction::MCOPY => {
+            flow::mcopy(state);
+            None
+        }
 
+        Instruction::PUSH0 => {
+            push!(state, M256::zero());
+            None
+        }
         Instruction::PUSH(v) => {
             push!(state, v);
             None
@@ -398,3 +474,9 @@ pub fn run_opcode<M: Memory, P: Patch>(
         }
     }
 }
+
+/// Target of a relative jump, whose offset is from the position
+/// after its immediates. (EIP4200)
+fn relative_target(next: usize, offset: i16) -> usize {
+    (next as isize + offset as isize) as usize
+}
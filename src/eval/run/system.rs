@@ -20,11 +20,20 @@ use core::cmp::min;
 #[cfg(feature = "std")]
 use std::cmp::min;
 
-use sha3::{Digest, Keccak256};
-
 pub fn suicide<M: Memory, P: Patch>(state: &mut State<M, P>) {
     pop!(state, address: Address);
     let balance = state.account_state.balance(state.context.address).unwrap();
+
+    // Accounts not created in the current transaction are not
+    // deleted, and only have their balance sent to the target.
+    if state.patch.has_restricted_suicide() && !state.account_state.is_created(state.context.address) {
+        if address != state.context.address {
+            state.account_state.increase_balance(address, balance);
+            state.account_state.decrease_balance(state.context.address, balance);
+        }
+        return;
+    }
+
     if !state.removed.contains(&state.context.address) {
         state.removed.push(state.context.address);
     }
@@ -52,8 +61,8 @@ pub fn log<M: Memory, P: Patch>(state: &mut State<M, P>, topic_len: usize) {
 pub fn sha3<M: Memory, P: Patch>(state: &mut State<M, P>) {
     pop!(state, from: U256, len: U256);
     let data = copy_from_memory(&state.memory, from, len);
-    let ret = Keccak256::digest(data.as_slice());
-    push!(state, M256::from(ret.as_slice()));
+    let ret = state.patch.crypto().keccak256(data.as_slice());
+    push!(state, ret.into());
 }
 
 macro_rules! try_callstack_limit {
@@ -92,29 +101,39 @@ pub fn create<M: Memory, P: Patch>(state: &mut State<M, P>, after_gas: Gas, is_c
         ValidTransaction {
             caller: Some(state.context.address),
             gas_price: state.context.gas_price,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
             gas_limit: l64_after_gas,
             value,
             input: init,
             action: TransactionAction::Create,
             nonce: state.account_state.nonce(state.context.address).unwrap(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: state.context.blob_versioned_hashes.clone(),
         }
     } else {
         pop!(state, salt: H256);
-        let init_hash = Keccak256::digest(&init);
-        let init_hash = M256::from(&init_hash[..]);
+        let init_hash = M256::from(state.patch.crypto().keccak256(&init));
         ValidTransaction {
             caller: Some(state.context.address),
             gas_price: state.context.gas_price,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
             gas_limit: l64_after_gas,
             value,
             input: init,
             action: TransactionAction::Create2(salt, init_hash),
             nonce: state.account_state.nonce(state.context.address).unwrap(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: state.context.blob_versioned_hashes.clone(),
         }
     };
 
     let context = transaction
-        .into_context::<P>(
+        .into_context_with_patch(
+            state.patch,
             Gas::zero(),
             Some(state.context.origin),
             &mut state.account_state,
@@ -123,6 +142,10 @@ pub fn create<M: Memory, P: Patch>(state: &mut State<M, P>, after_gas: Gas, is_c
         )
         .unwrap();
 
+    if state.patch.has_warm_cold_access() {
+        state.account_state.access_address(context.address);
+    }
+
     push!(state, context.address.into());
     Some(Control::InvokeCreate(context))
 }
@@ -150,15 +173,21 @@ pub fn call<M: Memory, P: Patch>(
     let transaction = ValidTransaction {
         caller: Some(state.context.address),
         gas_price: state.context.gas_price,
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
         gas_limit,
         value,
         input,
         action: TransactionAction::Call(to),
         nonce: state.account_state.nonce(state.context.address).unwrap(),
+        access_list: Vec::new(),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: state.context.blob_versioned_hashes.clone(),
     };
 
     let mut context = transaction
-        .into_context::<P>(
+        .into_context_with_patch(
+            state.patch,
             Gas::zero(),
             Some(state.context.origin),
             &mut state.account_state,
@@ -191,15 +220,21 @@ pub fn static_call<M: Memory, P: Patch>(state: &mut State<M, P>, stipend_gas: Ga
     let transaction = ValidTransaction {
         caller: Some(state.context.address),
         gas_price: state.context.gas_price,
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
         gas_limit,
         value: U256::zero(),
         input,
         action: TransactionAction::Call(to),
         nonce: state.account_state.nonce(state.context.address).unwrap(),
+        access_list: Vec::new(),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: state.context.blob_versioned_hashes.clone(),
     };
 
     let context = transaction
-        .into_context::<P>(
+        .into_context_with_patch(
+            state.patch,
             Gas::zero(),
             Some(state.context.origin),
             &mut state.account_state,
@@ -229,15 +264,21 @@ pub fn delegate_call<M: Memory, P: Patch>(state: &mut State<M, P>, after_gas: Ga
     let transaction = ValidTransaction {
         caller: Some(state.context.caller),
         gas_price: state.context.gas_price,
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
         gas_limit,
         value: state.context.value,
         input,
         action: TransactionAction::Call(to),
         nonce: state.account_state.nonce(state.context.address).unwrap(),
+        access_list: Vec::new(),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: state.context.blob_versioned_hashes.clone(),
     };
 
     let mut context = transaction
-        .into_context::<P>(
+        .into_context_with_patch(
+            state.patch,
             Gas::zero(),
             Some(state.context.origin),
             &mut state.account_state,
@@ -0,0 +1,1198 @@
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use bigint::{Address, Gas, H256, M256, U256};
+use hexutil::read_hex;
+use smallvec::SmallVec;
+
+use crate::errors::{EvalError, RuntimeError};
+use crate::*;
+
+/// Istanbul-like patch without precompiled contracts. Tests enable
+/// the features of later forks on top of it.
+pub(crate) fn test_patch() -> DynamicPatch {
+    DynamicPatch {
+        account_patch: DynamicAccountPatch {
+            initial_nonce: U256::zero(),
+            initial_create_nonce: U256::one(),
+            empty_considered_exists: false,
+            allow_partial_change: false,
+        },
+        code_deposit_limit: Some(0x6000),
+        callstack_limit: 1024,
+        gas_extcode: Gas::from(700usize),
+        gas_balance: Gas::from(700usize),
+        gas_extcodehash: Gas::from(700usize),
+        gas_sload: Gas::from(800usize),
+        gas_suicide: Gas::from(5000usize),
+        gas_suicide_new_account: Gas::from(25000usize),
+        gas_call: Gas::from(700usize),
+        gas_expbyte: Gas::from(50usize),
+        gas_transaction_create: Gas::from(32000usize),
+        force_code_deposit: false,
+        has_delegate_call: true,
+        has_static_call: true,
+        has_revert: true,
+        has_return_data: true,
+        has_bitwise_shift: true,
+        has_extcodehash: true,
+        has_create2: true,
+        has_reduced_sstore_gas_metering: false,
+        has_net_sstore_gas_metering: true,
+        has_warm_cold_access: false,
+        gas_warm_access: Gas::from(100usize),
+        gas_cold_account_access: Gas::from(2600usize),
+        gas_cold_sload: Gas::from(2100usize),
+        has_chain_id: true,
+        chain_id: U256::one(),
+        has_self_balance: true,
+        has_base_fee: false,
+        max_refund_quotient: NonZeroUsize::new(2).unwrap(),
+        refund_sstore_clears: Gas::from(15000usize),
+        refund_suicide: Gas::from(24000usize),
+        has_push0: false,
+        initcode_limit: None,
+        gas_initcode_word: Gas::zero(),
+        has_transient_storage: false,
+        has_mcopy: false,
+        has_restricted_suicide: false,
+        has_ef_code_rejection: false,
+        has_blobs: false,
+        blob_base_fee_update_fraction: NonZeroU64::new(3338477).unwrap(),
+        has_prev_randao: false,
+        has_eof: false,
+        err_on_call_with_more_gas: false,
+        call_create_l64_after_gas: true,
+        memory_limit: usize::MAX,
+        enabled_precompileds: SmallVec::new(),
+        precompileds: Arc::new(PrecompiledRegistry::new()),
+        crypto: Arc::new(NativeCryptoProvider),
+    }
+}
+
+fn header() -> HeaderParams {
+    HeaderParams {
+        beneficiary: Address::default(),
+        timestamp: 0,
+        number: U256::zero(),
+        difficulty: U256::zero(),
+        prev_randao: H256::default(),
+        gas_limit: Gas::max_value(),
+        base_fee: Gas::zero(),
+        excess_blob_gas: Gas::zero(),
+    }
+}
+
+const CONTRACT: u64 = 0x1000;
+const CALLER: u64 = 0x2000;
+
+fn context(code: &str, gas_limit: usize) -> Context {
+    Context {
+        address: Address::from(CONTRACT),
+        caller: Address::from(CALLER),
+        callee: Address::from(CONTRACT),
+        code: Rc::new(read_hex(code).unwrap()),
+        data: Rc::new(Vec::new()),
+        gas_limit: Gas::from(gas_limit),
+        gas_price: Gas::zero(),
+        origin: Address::from(CALLER),
+        value: U256::zero(),
+        apprent_value: U256::zero(),
+        is_system: false,
+        is_static: false,
+        blob_versioned_hashes: Rc::new(Vec::new()),
+    }
+}
+
+/// Account committed to the VM when it is required, with a nonce of
+/// one so that it is never empty. Accounts not in the list do not
+/// exist.
+struct TestAccount {
+    address: Address,
+    balance: U256,
+    code: &'static str,
+    storage: Vec<(U256, M256)>,
+}
+
+impl TestAccount {
+    fn new(address: u64) -> Self {
+        TestAccount {
+            address: Address::from(address),
+            balance: U256::zero(),
+            code: "",
+            storage: Vec::new(),
+        }
+    }
+
+    fn balance(mut self, balance: u64) -> Self {
+        self.balance = U256::from(balance);
+        self
+    }
+
+    fn code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+
+    fn storage(mut self, index: u64, value: u64) -> Self {
+        self.storage.push((U256::from(index), M256::from(value)));
+        self
+    }
+}
+
+/// Run the VM to the end, committing the required accounts.
+fn fire<V: VM>(vm: &mut V, accounts: &[TestAccount]) {
+    let find = |address: Address| accounts.iter().find(|account| account.address == address);
+    loop {
+        let commitment = match vm.fire() {
+            Ok(()) => return,
+            Err(RequireError::Account(address)) => match find(address) {
+                Some(account) => AccountCommitment::Full {
+                    nonce: U256::one(),
+                    address,
+                    balance: account.balance,
+                    code: Rc::new(read_hex(account.code).unwrap()),
+                },
+                None => AccountCommitment::Nonexist(address),
+            },
+            Err(RequireError::AccountCode(address)) => AccountCommitment::Code {
+                address,
+                code: Rc::new(
+                    find(address)
+                        .map(|account| read_hex(account.code).unwrap())
+                        .unwrap_or_default(),
+                ),
+            },
+            Err(RequireError::AccountStorage(address, index)) => AccountCommitment::Storage {
+                address,
+                index,
+                value: find(address)
+                    .and_then(|account| account.storage.iter().find(|&&(i, _)| i == index))
+                    .map(|&(_, value)| value)
+                    .unwrap_or_default(),
+            },
+            Err(RequireError::Blockhash(number)) => {
+                vm.commit_blockhash(number, H256::default()).unwrap();
+                continue;
+            }
+        };
+        vm.commit_account(commitment).unwrap();
+    }
+}
+
+/// Run the code as the contract, returning the VM.
+fn run<'a>(
+    patch: &'a DynamicPatch,
+    code: &str,
+    gas_limit: usize,
+    accounts: &[TestAccount],
+) -> SeqContextVM<'a, DynamicPatch> {
+    let mut vm = SeqContextVM::new(patch, context(code, gas_limit), header());
+    fire(&mut vm, accounts);
+    vm
+}
+
+/// Gas used by the VM before refunds.
+fn used_gas<V: VM>(vm: &V, gas_limit: usize) -> Gas {
+    Gas::from(gas_limit) - vm.available_gas()
+}
+
+/// Transaction from the caller with the given action and input, a
+/// zero gas price and a gas limit of 1000000.
+fn transaction(action: TransactionAction, input: &str) -> ValidTransaction {
+    ValidTransaction {
+        caller: Some(Address::from(CALLER)),
+        gas_price: Gas::zero(),
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        gas_limit: Gas::from(1000000u64),
+        action,
+        value: U256::zero(),
+        input: Rc::new(read_hex(input).unwrap()),
+        nonce: U256::zero(),
+        access_list: Vec::new(),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: Rc::new(Vec::new()),
+    }
+}
+
+/// Balance of an account after a transaction.
+fn balance(vm: &SeqTransactionVM<DynamicPatch>, address: u64) -> U256 {
+    vm.current_state()
+        .unwrap()
+        .account_state
+        .balance(Address::from(address))
+        .unwrap()
+}
+
+#[test]
+fn eip2200_sstore_gas() {
+    // Code, original value, used gas and refund from the EIP2200 test cases
+    let cases: &[(&str, u64, usize, usize)] = &[
+        ("0x60006000556000600055", 0, 1612, 0),
+        ("0x60006000556001600055", 0, 20812, 0),
+        ("0x60016000556000600055", 0, 20812, 19200),
+        ("0x60016000556002600055", 0, 20812, 0),
+        ("0x60016000556001600055", 0, 20812, 0),
+        ("0x60006000556000600055", 1, 5812, 15000),
+        ("0x60006000556001600055", 1, 5812, 4200),
+        ("0x60006000556002600055", 1, 5812, 0),
+        ("0x60026000556000600055", 1, 5812, 15000),
+        ("0x60026000556003600055", 1, 5812, 0),
+        ("0x60026000556001600055", 1, 5812, 4200),
+        ("0x60026000556002600055", 1, 5812, 0),
+        ("0x60016000556000600055", 1, 5812, 15000),
+        ("0x60016000556002600055", 1, 5812, 0),
+        ("0x60016000556001600055", 1, 1612, 0),
+        ("0x600160005560006000556001600055", 0, 40818, 19200),
+        ("0x600060005560016000556000600055", 1, 10818, 19200),
+    ];
+
+    let patch = test_patch();
+    for &(code, original, gas, refund) in cases {
+        let accounts = [TestAccount::new(CONTRACT).storage(0, original)];
+        let vm = run(&patch, code, 100000, &accounts);
+        assert_eq!(vm.status(), VMStatus::ExitedOk, "{}", code);
+        assert_eq!(used_gas(&vm, 100000), Gas::from(gas), "{}", code);
+        assert_eq!(vm.refunded_gas(), Gas::from(refund), "{}", code);
+    }
+}
+
+#[test]
+fn eip2200_sstore_sentry() {
+    // PUSH1 0, PUSH1 0, SSTORE with 2300 gas left at the SSTORE
+    let patch = test_patch();
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let vm = run(&patch, "0x6000600055", 2306, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::EmptyGas));
+    assert_eq!(vm.available_gas(), Gas::zero());
+
+    let vm = run(&patch, "0x6000600055", 2307, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(used_gas(&vm, 2307), Gas::from(806usize));
+}
+
+#[test]
+fn eip2929_cold_and_warm_access() {
+    // Code and used gas, with the second access to a slot or an
+    // account being warm
+    let cases: &[(&str, usize)] = &[
+        // SLOAD 0 twice
+        ("0x6000545060005450", 3 + 2100 + 2 + 3 + 100 + 2),
+        // BALANCE 0x3000 twice
+        ("0x61300031506130003150", 3 + 2600 + 2 + 3 + 100 + 2),
+        // EXTCODESIZE, EXTCODEHASH and EXTCODECOPY of 0x3000
+        (
+            "0x6130003b506130003f506000600060006130003c",
+            3 + 2600 + 2 + 3 + 100 + 2 + 4 * 3 + 100,
+        ),
+        // BALANCE of the executing address is always warm
+        ("0x303150", 2 + 100 + 2),
+    ];
+
+    let mut patch = test_patch();
+    patch.has_warm_cold_access = true;
+    for &(code, gas) in cases {
+        let accounts = [TestAccount::new(CONTRACT), TestAccount::new(0x3000).code("0x00")];
+        let vm = run(&patch, code, 100000, &accounts);
+        assert_eq!(vm.status(), VMStatus::ExitedOk, "{}", code);
+        assert_eq!(used_gas(&vm, 100000), Gas::from(gas), "{}", code);
+    }
+}
+
+#[test]
+fn eip2929_access_list_prewarming() {
+    // SLOAD 0, BALANCE 0x3000
+    let code = "0x600054506130003150";
+    let transaction = |access_list| ValidTransaction {
+        caller: Some(Address::from(CALLER)),
+        gas_price: Gas::zero(),
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        gas_limit: Gas::from(100000u64),
+        action: TransactionAction::Call(Address::from(CONTRACT)),
+        value: U256::zero(),
+        input: Rc::new(Vec::new()),
+        nonce: U256::zero(),
+        access_list,
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: Rc::new(Vec::new()),
+    };
+    let accounts = [
+        TestAccount::new(CALLER),
+        TestAccount::new(CONTRACT).code(code),
+        TestAccount::new(0x3000),
+    ];
+
+    let mut patch = test_patch();
+    patch.has_warm_cold_access = true;
+
+    let mut vm = SeqTransactionVM::new(&patch, transaction(Vec::new()), header()).unwrap();
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.used_gas(), Gas::from(21000 + 3 + 2100 + 2 + 3 + 2600 + 2u64));
+
+    let access_list = vec![
+        AccessListItem {
+            address: Address::from(CONTRACT),
+            storage_keys: vec![U256::zero()],
+        },
+        AccessListItem {
+            address: Address::from(0x3000),
+            storage_keys: Vec::new(),
+        },
+    ];
+    let mut vm = SeqTransactionVM::new(&patch, transaction(access_list), header()).unwrap();
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(
+        vm.used_gas(),
+        Gas::from(21000 + 2 * 2400 + 1900 + 3 + 100 + 2 + 3 + 100 + 2u64)
+    );
+}
+
+#[test]
+fn eip1559_base_fee() {
+    // Return BASEFEE
+    let code = "0x4860005260206000f3";
+    let accounts = [
+        TestAccount::new(CALLER).balance(100000000),
+        TestAccount::new(CONTRACT).code(code),
+    ];
+    let block = HeaderParams {
+        beneficiary: Address::from(0x7000),
+        base_fee: Gas::from(7u64),
+        ..header()
+    };
+    let used_gas = 21000 + 2 + 3 + 6 + 3 + 3u64;
+
+    let mut patch = test_patch();
+    patch.has_base_fee = true;
+    // A legacy transaction paying 10 per gas, and a fee market one
+    // paying the base fee and a priority fee of 2
+    let legacy = ValidTransaction {
+        gas_price: Gas::from(10u64),
+        ..transaction(TransactionAction::Call(Address::from(CONTRACT)), "0x")
+    };
+    let fee_market = ValidTransaction {
+        max_fee_per_gas: Some(Gas::from(20u64)),
+        max_priority_fee_per_gas: Some(Gas::from(2u64)),
+        ..transaction(TransactionAction::Call(Address::from(CONTRACT)), "0x")
+    };
+    for &(transaction, price, reward) in &[(&legacy, 10, 3), (&fee_market, 9, 2)] {
+        let mut vm = SeqTransactionVM::new(&patch, transaction.clone(), block.clone()).unwrap();
+        fire(&mut vm, &accounts);
+        assert_eq!(vm.status(), VMStatus::ExitedOk);
+        assert_eq!(vm.out(), word(7).as_slice());
+        assert_eq!(vm.used_gas(), Gas::from(used_gas));
+        // The base fee part of the fee is burnt
+        assert_eq!(balance(&vm, CALLER), U256::from(100000000 - used_gas * price));
+        assert_eq!(balance(&vm, 0x7000), U256::from(used_gas * reward));
+    }
+}
+
+#[test]
+fn eip4844_blobhash() {
+    // Return BLOBHASH 1 and BLOBHASH 2
+    let code = "0x60014960005260024960205260406000f3";
+    let accounts = [TestAccount::new(CONTRACT)];
+    let hashes = vec![H256::from(0x0100u64), H256::from(0x0101u64)];
+    let context = Context {
+        blob_versioned_hashes: Rc::new(hashes.clone()),
+        ..context(code, 100000)
+    };
+
+    let mut patch = test_patch();
+    let mut vm = SeqContextVM::new(&patch, context.clone(), header());
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::InvalidOpcode));
+
+    // Indices past the last blob return zero
+    patch.has_blobs = true;
+    let mut vm = SeqContextVM::new(&patch, context, header());
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    let mut expected = hashes[1].to_vec();
+    expected.extend(word(0));
+    assert_eq!(vm.out(), expected.as_slice());
+}
+
+#[test]
+fn eip7516_blobbasefee() {
+    // Return BLOBBASEFEE
+    let code = "0x4a60005260206000f3";
+    let accounts = [TestAccount::new(CONTRACT)];
+    let block = HeaderParams {
+        excess_blob_gas: Gas::from(10_000_000u64),
+        ..header()
+    };
+
+    let mut patch = test_patch();
+    let mut vm = SeqContextVM::new(&patch, context(code, 100000), block.clone());
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::InvalidOpcode));
+
+    patch.has_blobs = true;
+    let mut vm = SeqContextVM::new(&patch, context(code, 100000), block);
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(19).as_slice());
+    assert_eq!(used_gas(&vm, 100000), Gas::from(2 + 3 + 6 + 3 + 3usize));
+}
+
+#[test]
+fn eip4844_blob_fee() {
+    let accounts = [TestAccount::new(CALLER).balance(100000000), TestAccount::new(CONTRACT)];
+    // A blob base fee of 19
+    let block = HeaderParams {
+        beneficiary: Address::from(0x7000),
+        excess_blob_gas: Gas::from(10_000_000u64),
+        ..header()
+    };
+    let blob_transaction = |max_fee_per_blob_gas: u64| ValidTransaction {
+        gas_price: Gas::one(),
+        max_fee_per_blob_gas: Some(Gas::from(max_fee_per_blob_gas)),
+        blob_versioned_hashes: Rc::new(vec![H256::from(0x0100u64), H256::from(0x0101u64)]),
+        ..transaction(TransactionAction::Call(Address::from(CONTRACT)), "0x")
+    };
+
+    let mut patch = test_patch();
+    patch.has_blobs = true;
+    match SeqTransactionVM::new(&patch, blob_transaction(18), block.clone()) {
+        Err(PreExecutionError::InvalidFeeCap) => (),
+        _ => panic!(),
+    }
+
+    // The blob fee is paid at the blob base fee, not the maximum, and
+    // is burnt instead of going to the beneficiary
+    let mut vm = SeqTransactionVM::new(&patch, blob_transaction(20), block).unwrap();
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.used_gas(), Gas::from(21000u64));
+    let blob_fee = 2 * 131072 * 19;
+    assert_eq!(balance(&vm, CALLER), U256::from(100000000 - 21000 - blob_fee));
+    assert_eq!(balance(&vm, 0x7000), U256::from(21000));
+}
+
+#[test]
+fn eip4399_prev_randao() {
+    // Return DIFFICULTY
+    let code = "0x4460005260206000f3";
+    let accounts = [TestAccount::new(CONTRACT)];
+    let block = HeaderParams {
+        difficulty: U256::from(5u64),
+        prev_randao: H256::from(0x1234u64),
+        ..header()
+    };
+
+    let mut patch = test_patch();
+    let mut vm = SeqContextVM::new(&patch, context(code, 100000), block.clone());
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(5).as_slice());
+
+    patch.has_prev_randao = true;
+    let mut vm = SeqContextVM::new(&patch, context(code, 100000), block);
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(0x1234).as_slice());
+}
+
+#[test]
+fn eip1344_eip1884_chain_id_self_balance() {
+    // Return CHAINID and SELFBALANCE
+    let code = "0x466000524760205260406000f3";
+    let accounts = [TestAccount::new(CONTRACT).balance(77)];
+
+    let mut patch = test_patch();
+    patch.chain_id = U256::from(61u64);
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    let mut expected = word(61);
+    expected.extend(word(77));
+    assert_eq!(vm.out(), expected.as_slice());
+    assert_eq!(used_gas(&vm, 100000), Gas::from(2 + 3 + 6 + 5 + 3 + 6 + 3 + 3usize));
+
+    patch.has_chain_id = false;
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::InvalidOpcode));
+
+    // SELFBALANCE
+    patch.has_self_balance = false;
+    let vm = run(&patch, "0x47", 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::InvalidOpcode));
+}
+
+#[test]
+fn eip3529_refund_cap() {
+    // Clear slots 0, 1 and 2
+    let code = "0x600060005560006001556000600255";
+    let accounts = [TestAccount::new(CONTRACT).storage(0, 1).storage(1, 1).storage(2, 1)];
+    let used = 3 * (3 + 3 + 5000usize);
+
+    // Before EIP3529, the refund of 45000 is capped at half the used gas
+    let mut patch = test_patch();
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(used_gas(&vm, 100000), Gas::from(used));
+    assert_eq!(vm.refunded_gas(), Gas::from(45000usize));
+    assert_eq!(vm.used_gas(), Gas::from(used - used / 2));
+
+    // After, a refund of 14400 is capped at a fifth of it
+    patch.max_refund_quotient = NonZeroUsize::new(5).unwrap();
+    patch.refund_sstore_clears = Gas::from(4800usize);
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.refunded_gas(), Gas::from(14400usize));
+    assert_eq!(vm.used_gas(), Gas::from(used - used / 5));
+}
+
+#[test]
+fn eip3529_suicide_refund() {
+    // SELFDESTRUCT to 0x3000
+    let code = "0x613000ff";
+    let accounts = [TestAccount::new(CONTRACT), TestAccount::new(0x3000)];
+
+    let mut patch = test_patch();
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.refunded_gas(), Gas::from(24000usize));
+
+    patch.refund_suicide = Gas::zero();
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.refunded_gas(), Gas::zero());
+    assert_eq!(vm.used_gas(), used_gas(&vm, 100000));
+}
+
+#[test]
+fn eip2929_reverted_call_unwarms() {
+    // CALL 0x3000 with all gas, then BALANCE 0x4000
+    let code = "0x600060006000600060006130005af1506140003150";
+    let caller_gas: usize = 5 * 3 + 3 + 2 + 2600 + 2 + 3 + 2;
+    // BALANCE 0x4000, then REVERT or STOP
+    let callees = [("0x614000315060006000fd", true), ("0x614000315000", false)];
+
+    let mut patch = test_patch();
+    patch.has_warm_cold_access = true;
+    for &(callee, reverted) in &callees {
+        let accounts = [
+            TestAccount::new(CONTRACT),
+            TestAccount::new(0x3000).code(callee),
+            TestAccount::new(0x4000),
+        ];
+        let vm = run(&patch, code, 100000, &accounts);
+        assert_eq!(vm.status(), VMStatus::ExitedOk);
+
+        // The call target is warmed by the caller and stays warm. The
+        // callee pays for the cold access to 0x4000 either way, and
+        // the caller pays for it again after a revert.
+        assert!(vm
+            .current_state()
+            .account_state
+            .is_address_accessed(Address::from(0x3000)));
+        let (callee_gas, balance_gas) = if reverted {
+            (3 + 2600 + 2 + 2 * 3, 2600)
+        } else {
+            (3 + 2600 + 2, 100)
+        };
+        assert_eq!(used_gas(&vm, 100000), Gas::from(caller_gas + callee_gas + balance_gas));
+    }
+}
+
+#[test]
+fn eip3860_create_initcode_limit() {
+    // CREATE with 0xc001 and 0xc000 bytes of zero init code
+    let oversized = "0x61c00160006000f0";
+    let limit = "0x61c00060006000f0";
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let mut patch = test_patch();
+    let vm = run(&patch, oversized, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+
+    patch.initcode_limit = Some(0xc000);
+    patch.gas_initcode_word = Gas::from(2usize);
+    let vm = run(&patch, oversized, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::EmptyGas));
+    assert_eq!(vm.available_gas(), Gas::zero());
+    let vm = run(&patch, limit, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+}
+
+#[test]
+fn eip3860_create_initcode_word_cost() {
+    // CREATE with 65 bytes of zero init code
+    let code = "0x604160006000f0";
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    // The word cost is only charged when the init code is limited
+    let mut patch = test_patch();
+    patch.gas_initcode_word = Gas::from(2usize);
+    let unlimited = used_gas(&run(&patch, code, 100000, &accounts), 100000);
+
+    patch.initcode_limit = Some(0xc000);
+    let limited = used_gas(&run(&patch, code, 100000, &accounts), 100000);
+    assert_eq!(limited, unlimited + Gas::from(3 * 2usize));
+}
+
+#[test]
+fn eip3860_transaction_initcode_limit() {
+    let create = |len: usize, gas_limit: u64| UntrustedTransaction {
+        caller: AccountCommitment::Full {
+            nonce: U256::zero(),
+            address: Address::from(CALLER),
+            balance: U256::zero(),
+            code: Rc::new(Vec::new()),
+        },
+        gas_price: Gas::zero(),
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        gas_limit: Gas::from(gas_limit),
+        action: TransactionAction::Create,
+        value: U256::zero(),
+        input: Rc::new(vec![0u8; len]),
+        access_list: Vec::new(),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: Rc::new(Vec::new()),
+    };
+    // Intrinsic gas of 0xc000 bytes of zero init code, without the
+    // 0x600 words
+    let intrinsic = 21000 + 32000 + 4 * 0xc000;
+
+    let mut patch = test_patch();
+    patch.gas_initcode_word = Gas::from(2usize);
+    assert!(create(0xc001, 1000000).to_valid(&patch).is_ok());
+    assert!(create(0xc000, intrinsic).to_valid(&patch).is_ok());
+
+    patch.initcode_limit = Some(0xc000);
+    match create(0xc001, 1000000).to_valid(&patch) {
+        Err(PreExecutionError::InitcodeSizeExceeded) => (),
+        _ => panic!(),
+    }
+    match create(0xc000, intrinsic).to_valid(&patch) {
+        Err(PreExecutionError::InsufficientGasLimit) => (),
+        _ => panic!(),
+    }
+    let valid = create(0xc000, intrinsic + 2 * 0x600).to_valid(&patch).unwrap();
+    assert_eq!(valid.intrinsic_gas_with_patch(&patch), Gas::from(intrinsic + 2 * 0x600));
+}
+
+#[test]
+fn eip3855_push0() {
+    // Return ISZERO(PUSH0)
+    let code = "0x5f1560005260206000f3";
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let mut patch = test_patch();
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::InvalidOpcode));
+
+    patch.has_push0 = true;
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(1).as_slice());
+    assert_eq!(used_gas(&vm, 100000), Gas::from(2 + 3 + 3 + 6 + 3 + 3usize));
+}
+
+#[test]
+fn eip3541_create_ef_code() {
+    // CREATE with init code returning 0xef, returning the created
+    // address
+    let code = "0x6960ef60005360016000f3600052600a60166000f060005260206000f3";
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let mut patch = test_patch();
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_ne!(vm.out(), word(0).as_slice());
+
+    // The creation fails, but not the creating contract
+    patch.has_ef_code_rejection = true;
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(0).as_slice());
+}
+
+#[test]
+fn eip3541_transaction_ef_code() {
+    // Init code returning 0xef
+    let create = transaction(TransactionAction::Create, "0x60ef60005360016000f3");
+    let accounts = [TestAccount::new(CALLER)];
+
+    let mut patch = test_patch();
+    // The fee paid to the beneficiary, which is never required, is
+    // left as a partial change
+    patch.account_patch.allow_partial_change = true;
+    let address = create.address_with_crypto(patch.crypto());
+    let mut vm = SeqTransactionVM::new(&patch, create.clone(), header()).unwrap();
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    let code = vm.current_state().unwrap().account_state.code(address).unwrap();
+    assert_eq!(code.as_slice(), &[0xef]);
+
+    patch.has_ef_code_rejection = true;
+    let mut vm = SeqTransactionVM::new(&patch, create, header()).unwrap();
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::InvalidCode));
+}
+
+/// 32-byte big-endian word of a value, as returned by the VM.
+fn word(value: u64) -> Vec<u8> {
+    let mut word = vec![0u8; 32];
+    U256::from(value).to_big_endian(&mut word);
+    word
+}
+
+#[test]
+fn eip1153_transient_storage_dropped_on_revert() {
+    // Call 0x3000 with one byte of call data, then twice without it,
+    // returning the output of the last two calls
+    let code = concat!(
+        "0x602060006001600060006130005af150",
+        "602060006000600060006130005af150",
+        "602060206000600060006130005af150",
+        "60406000f3"
+    );
+    // Return TLOAD 0, after setting it to 1 with TSTORE, and revert
+    // instead if there is call data
+    let callee = "0x60005c600052600160005d3660145760206000f35b60206000fd";
+    let accounts = [TestAccount::new(CONTRACT), TestAccount::new(0x3000).code(callee)];
+
+    let mut patch = test_patch();
+    patch.has_transient_storage = true;
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), [word(0), word(1)].concat().as_slice());
+}
+
+#[test]
+fn eip1153_transient_storage_cleared_between_transactions() {
+    // TSTORE 0 1, then return TLOAD 0
+    let write = "0x600160005d60005c60005260206000f3";
+    // Return TLOAD 0
+    let read = "0x60005c60005260206000f3";
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let mut patch = test_patch();
+    patch.has_transient_storage = true;
+    let vm = run(&patch, write, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(1).as_slice());
+
+    let mut next = SeqContextVM::with_previous(&patch, context(read, 100000), header(), &vm);
+    fire(&mut next, &accounts);
+    assert_eq!(next.status(), VMStatus::ExitedOk);
+    assert_eq!(next.out(), word(0).as_slice());
+}
+
+#[test]
+fn eip1153_tstore_in_static_call() {
+    // STATICCALL 0x3000 and 0x4000, returning both results
+    let code = concat!(
+        "0x60006000600060006130005afa600052",
+        "60006000600060006140005afa602052",
+        "60406000f3"
+    );
+    let accounts = [
+        TestAccount::new(CONTRACT),
+        // TSTORE 0 1
+        TestAccount::new(0x3000).code("0x600160005d00"),
+        // TLOAD 0
+        TestAccount::new(0x4000).code("0x60005c5000"),
+    ];
+
+    let mut patch = test_patch();
+    patch.has_transient_storage = true;
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), [word(0), word(1)].concat().as_slice());
+}
+
+#[test]
+fn eip5656_mcopy_memory_expansion() {
+    let mut patch = test_patch();
+    patch.has_mcopy = true;
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    // MSTORE 0x20 1, then MCOPY 0 0x20 0x20 and return the first word
+    let code = "0x60016020526020602060005e60206000f3";
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(1).as_slice());
+    // The copy stays within the two words expanded by MSTORE
+    assert_eq!(used_gas(&vm, 100000), Gas::from(15 + 9 + 6 + 6usize));
+
+    // MCOPY 0x40 0 0x20, expanding the memory to three words
+    let vm = run(&patch, "0x6020600060405e", 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(used_gas(&vm, 100000), Gas::from(9 + 6 + 9usize));
+
+    // MCOPY from 0x40 to 0 of 0x20 bytes, expanding by the source
+    let vm = run(&patch, "0x6020604060005e", 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(used_gas(&vm, 100000), Gas::from(9 + 6 + 9usize));
+
+    // Zero length copy at huge offsets, which does not expand memory
+    let code = concat!(
+        "0x6000",
+        "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "5e"
+    );
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(used_gas(&vm, 100000), Gas::from(9 + 3usize));
+}
+
+#[test]
+fn eip6780_suicide_of_existing_account() {
+    // SUICIDE to 0x3000
+    let accounts = [TestAccount::new(CONTRACT).balance(100), TestAccount::new(0x3000)];
+    let balance = |vm: &SeqContextVM<DynamicPatch>, address: u64| {
+        vm.current_state()
+            .account_state
+            .balance(Address::from(address))
+            .unwrap()
+    };
+
+    let mut patch = test_patch();
+    let vm = run(&patch, "0x613000ff", 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.removed(), &[Address::from(CONTRACT)]);
+
+    // Only the balance is sent, and the account is kept
+    patch.has_restricted_suicide = true;
+    let vm = run(&patch, "0x613000ff", 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert!(vm.removed().is_empty());
+    assert_eq!(balance(&vm, CONTRACT), U256::zero());
+    assert_eq!(balance(&vm, 0x3000), U256::from(100u64));
+    assert_eq!(vm.refunded_gas(), Gas::zero());
+}
+
+#[test]
+fn eip6780_suicide_of_created_account() {
+    // CREATE with the init code SUICIDE to 0x3000
+    let code = "0x63613000ff6000526004601c6000f000";
+    let accounts = [TestAccount::new(CONTRACT), TestAccount::new(0x3000)];
+
+    let mut patch = test_patch();
+    patch.has_restricted_suicide = true;
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.removed().len(), 1);
+    assert_ne!(vm.removed()[0], Address::from(CONTRACT));
+}
+
+/// Encode an EOF container from the types of its code sections, and
+/// its code and data sections in hex.
+fn eof_container(types: &[(u8, u8, u16)], sections: &[&str], data: &str) -> String {
+    let mut code = format!("0xef000101{:04x}02{:04x}", types.len() * 4, sections.len());
+    for section in sections {
+        code += &format!("{:04x}", section.len() / 2);
+    }
+    code += &format!("04{:04x}00", data.len() / 2);
+    for &(inputs, outputs, max_stack_height) in types {
+        code += &format!("{:02x}{:02x}{:04x}", inputs, outputs, max_stack_height);
+    }
+    code + &sections.concat() + data
+}
+
+#[test]
+fn eof_recursive_function() {
+    // Return f(10), where f(n) = n + f(n - 1) and f(0) = 0
+    let code = eof_container(
+        &[(0, 0, 2), (1, 1, 3)],
+        &["600ae3000160005260206000f3", "80e10001e48060019003e3000101e4"],
+        "",
+    );
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let mut patch = test_patch();
+    patch.has_eof = true;
+    patch.has_push0 = true;
+    let vm = run(&patch, &code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(55).as_slice());
+}
+
+#[test]
+fn eof_jump_table() {
+    // RJUMPV on the case with three branches and a default, each
+    // pushing its value before a common tail that returns it
+    let section = |case: u8| {
+        format!(
+            "60{:02x}e2020005000a000f60dde0000c60a0e0000760a1e0000260a25f5260205ff3",
+            case
+        )
+    };
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let mut patch = test_patch();
+    patch.has_eof = true;
+    patch.has_push0 = true;
+    for &(case, value) in &[(0, 0xa0), (1, 0xa1), (2, 0xa2), (3, 0xdd), (0xff, 0xdd)] {
+        let code = eof_container(&[(0, 0, 2)], &[&section(case)], "");
+        let vm = run(&patch, &code, 100000, &accounts);
+        assert_eq!(vm.status(), VMStatus::ExitedOk, "{}", case);
+        assert_eq!(vm.out(), word(value).as_slice(), "{}", case);
+    }
+}
+
+#[test]
+fn eof_return_stack_limit() {
+    // Store the depth at memory 0 and CALLF a function which calls
+    // itself, decrementing it, until it reaches zero
+    let code = |depth: u16| {
+        eof_container(
+            &[(0, 0, 2), (0, 0, 2)],
+            &[
+                &format!("61{:04x}5f52e3000100", depth),
+                "5f5180e1000250e4600190035f52e30001e4",
+            ],
+            "",
+        )
+    };
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let mut patch = test_patch();
+    patch.has_eof = true;
+    patch.has_push0 = true;
+    let vm = run(&patch, &code(1023), 1000000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+
+    let vm = run(&patch, &code(1024), 1000000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::StackOverflow));
+}
+
+#[test]
+fn eof_creation() {
+    // Runtime code returning 42
+    let runtime = eof_container(&[(0, 0, 2)], &["602a5f5260205ff3"], "");
+    // Init code copying the runtime code from its data section and
+    // returning it
+    let init = |runtime: &str| {
+        let size = runtime.len() / 2 - 1;
+        let data_offset = 13 + 2 + 4 + 10;
+        eof_container(
+            &[(0, 0, 3)],
+            &[&format!("60{:02x}60{:02x}5f3960{:02x}5ff3", size, data_offset, size)],
+            &runtime[2..],
+        )
+    };
+    let transaction = |action, input: &str| ValidTransaction {
+        caller: Some(Address::from(CALLER)),
+        gas_price: Gas::zero(),
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        gas_limit: Gas::from(1000000u64),
+        action,
+        value: U256::zero(),
+        input: Rc::new(read_hex(input).unwrap()),
+        nonce: U256::zero(),
+        access_list: Vec::new(),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: Rc::new(Vec::new()),
+    };
+    let accounts = [TestAccount::new(CALLER)];
+
+    let mut patch = test_patch();
+    patch.has_eof = true;
+    patch.has_push0 = true;
+    // The fee paid to the beneficiary, which is never required, is
+    // carried over to the next transaction as a partial change
+    patch.account_patch.allow_partial_change = true;
+    let create = transaction(TransactionAction::Create, &init(&runtime));
+    let address = create.address_with_crypto(patch.crypto());
+    let mut vm = SeqTransactionVM::new(&patch, create, header()).unwrap();
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+
+    let call = transaction(TransactionAction::Call(address), "0x");
+    let mut next = SeqTransactionVM::with_previous(call, header(), &vm);
+    fire(&mut next, &accounts);
+    assert_eq!(next.status(), VMStatus::ExitedOk);
+    assert_eq!(next.out(), word(42).as_slice());
+
+    // EOF init code may not deploy legacy code
+    let create = transaction(TransactionAction::Create, &init("0x602a5f5260205ff3"));
+    let mut vm = SeqTransactionVM::new(&patch, create, header()).unwrap();
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::InvalidCode));
+}
+
+#[test]
+fn eof_invalid_code() {
+    let accounts = [TestAccount::new(CONTRACT)];
+    let mut patch = test_patch();
+    patch.has_eof = true;
+    patch.has_push0 = true;
+
+    // A container with a wrong max stack height fails on its first
+    // step, consuming all gas
+    let code = eof_container(&[(0, 0, 2)], &["5f00"], "");
+    let vm = run(&patch, &code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::InvalidCode));
+    assert_eq!(vm.available_gas(), Gas::zero());
+
+    // RJUMP is only defined in EOF code
+    let vm = run(&patch, "0xe0000000", 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedErr(OnChainError::InvalidOpcode));
+}
+
+/// Precompiled contract failing every call.
+struct FailingPrecompiled;
+
+impl Precompiled for FailingPrecompiled {
+    fn gas_and_step(&self, _: &[u8], _: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        Err(RuntimeError::OnChain(OnChainError::PrecompiledFailure))
+    }
+}
+
+/// Precompiled contract returning its caller, value and static flag,
+/// and the balance of the transaction origin, counting its calls.
+struct ContextPrecompiled(AtomicUsize);
+
+impl Precompiled for ContextPrecompiled {
+    fn gas_and_step(&self, _: &[u8], _: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        Err(RuntimeError::NotSupported(NotSupportedError::PrecompiledNotSupported))
+    }
+
+    fn gas_and_step_with_context(
+        &self,
+        context: &Context,
+        state: &dyn AccountStateRead,
+        _: &dyn CryptoProvider,
+    ) -> Result<(Gas, Rc<Vec<u8>>), EvalError> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        let balance = state.balance(context.origin)?;
+
+        let mut out = vec![0u8; 128];
+        out[12..32].copy_from_slice(&context.caller);
+        context.value.to_big_endian(&mut out[32..64]);
+        out[95] = context.is_static as u8;
+        balance.to_big_endian(&mut out[96..128]);
+        Ok((Gas::from(100usize), Rc::new(out)))
+    }
+}
+
+#[test]
+fn precompiled_context() {
+    // CALL 0x5000 with a value of 5, then STATICCALL it, returning
+    // the output of both calls
+    let code = concat!(
+        "0x608060006000600060056150006103e8f150",
+        "60806080600060006150006103e8fa50",
+        "6101006000f3"
+    );
+    let precompiled = Arc::new(ContextPrecompiled(AtomicUsize::new(0)));
+    let mut registry = PrecompiledRegistry::new();
+    registry.insert(Address::from(0x5000), None, precompiled.clone(), U256::zero());
+    let mut patch = test_patch();
+    patch.enabled_precompileds.push(Address::from(0x5000));
+    patch.precompileds = Arc::new(registry);
+
+    let mut vm = SeqContextVM::new(&patch, context(code, 100000), header());
+    // The origin is only required by the precompiled contract, whose
+    // call is left unchanged until it is committed
+    loop {
+        let commitment = match vm.fire() {
+            Err(RequireError::Account(address)) if address == Address::from(CALLER) => break,
+            Err(RequireError::Account(address)) if address == Address::from(CONTRACT) => AccountCommitment::Full {
+                nonce: U256::one(),
+                address,
+                balance: U256::from(5u64),
+                code: Rc::new(Vec::new()),
+            },
+            Err(RequireError::Account(address)) => AccountCommitment::Nonexist(address),
+            result => panic!("unexpected {:?}", result),
+        };
+        vm.commit_account(commitment).unwrap();
+    }
+    assert_eq!(precompiled.0.load(Ordering::SeqCst), 1);
+    vm.commit_account(AccountCommitment::Full {
+        nonce: U256::one(),
+        address: Address::from(CALLER),
+        balance: U256::from(77u64),
+        code: Rc::new(Vec::new()),
+    })
+    .unwrap();
+    vm.fire().unwrap();
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    // The failed call is retried once the origin is committed, and
+    // the static call finds it committed
+    assert_eq!(precompiled.0.load(Ordering::SeqCst), 3);
+
+    let mut expected = Vec::new();
+    for &(value, is_static) in &[(5, 0), (0, 1)] {
+        expected.extend(word(CONTRACT));
+        expected.extend(word(value));
+        expected.extend(word(is_static));
+        expected.extend(word(77));
+    }
+    assert_eq!(vm.out(), expected.as_slice());
+}
+
+#[test]
+fn precompiled_failure() {
+    // CALL 0x5000 with 1000 gas, returning the result
+    let code = "0x600060006000600060006150006103e8f160005260206000f3";
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let mut registry = PrecompiledRegistry::new();
+    registry.insert(Address::from(0x5000), None, Arc::new(FailingPrecompiled), U256::zero());
+    let mut patch = test_patch();
+    patch.enabled_precompileds.push(Address::from(0x5000));
+    patch.precompileds = Arc::new(registry);
+    let vm = run(&patch, code, 100000, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(0).as_slice());
+    // All of the gas given to the call is consumed
+    assert_eq!(used_gas(&vm, 100000), Gas::from(7 * 3 + 700 + 1000 + 9 + 6usize));
+}
+
+#[test]
+fn precompiled_activation_block() {
+    // CALL 0x5000 with 1000 gas, returning the result
+    let code = "0x600060006000600060006150006103e8f160005260206000f3";
+    let accounts = [TestAccount::new(CONTRACT)];
+
+    let mut registry = PrecompiledRegistry::new();
+    registry.insert(
+        Address::from(0x5000),
+        None,
+        Arc::new(FailingPrecompiled),
+        U256::from(100),
+    );
+    let mut patch = test_patch();
+    patch.enabled_precompileds.push(Address::from(0x5000));
+    patch.precompileds = Arc::new(registry);
+    assert!(patch.precompiled_addresses(U256::from(99)).is_empty());
+    assert_eq!(
+        patch.precompiled_addresses(U256::from(100)),
+        vec![Address::from(0x5000)]
+    );
+
+    // Before its activation block, the address is a plain account
+    let block = HeaderParams {
+        number: U256::from(99),
+        ..header()
+    };
+    let mut vm = SeqContextVM::new(&patch, context(code, 100000), block);
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(1).as_slice());
+
+    let block = HeaderParams {
+        number: U256::from(100),
+        ..header()
+    };
+    let mut vm = SeqContextVM::new(&patch, context(code, 100000), block);
+    fire(&mut vm, &accounts);
+    assert_eq!(vm.status(), VMStatus::ExitedOk);
+    assert_eq!(vm.out(), word(0).as_slice());
+}
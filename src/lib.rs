@@ -46,7 +46,7 @@
 //! use evm::{EmbeddedPatch, VMTestPatch,
 //!                 HeaderParams, ValidTransaction, TransactionAction,
 //!                 VM, SeqTransactionVM};
-//! use bigint::{Gas, U256, Address};
+//! use bigint::{Gas, U256, H256, Address};
 //! use std::rc::Rc;
 //!
 //! fn main() {
@@ -54,18 +54,26 @@
 //!   let transaction = ValidTransaction {
 //!     caller: Some(Address::default()),
 //!     gas_price: Gas::zero(),
+//!     max_fee_per_gas: None,
+//!     max_priority_fee_per_gas: None,
 //!     gas_limit: Gas::max_value(),
 //!     action: TransactionAction::Create,
 //!     value: U256::zero(),
 //!     input: Rc::new(Vec::new()),
-//!     nonce: U256::zero()
+//!     nonce: U256::zero(),
+//!     access_list: Vec::new(),
+//!     max_fee_per_blob_gas: None,
+//!     blob_versioned_hashes: Rc::new(Vec::new())
 //!   };
 //!   let header = HeaderParams {
 //!     beneficiary: Address::default(),
 //!     timestamp: 0,
 //!     number: U256::zero(),
 //!     difficulty: U256::zero(),
-//!     gas_limit: Gas::zero()
+//!     prev_randao: H256::default(),
+//!     gas_limit: Gas::zero(),
+//!     base_fee: Gas::zero(),
+//!     excess_blob_gas: Gas::zero(),
 //!   };
 //!   let cfg_before_500 = VMTestPatch::default();
 //!   let cfg_after_500 = EmbeddedPatch::default();
@@ -74,13 +82,13 @@
 //!       &cfg_before_500,
 //!       transaction,
 //!       header
-//!     );
+//!     ).unwrap();
 //!   } else {
 //!     SeqTransactionVM::new(
 //!       &cfg_after_500,
 //!       transaction,
 //!       header
-//!     );
+//!     ).unwrap();
 //!   };
 //! }
 //! ```
@@ -94,8 +102,10 @@
 //!
 //! Calling `TransactionVM::new` or `SeqTransactionVM::new` requires
 //! the transaction passed in to be valid (according to the rules for
-//! an Ethereum transaction). If the transaction is invalid, the VM
-//! will probably panic. If you want to handle untrusted transactions,
+//! an Ethereum transaction). Only its fee caps are checked against
+//! the block base fees, returning an error if they do not cover
+//! them. If the transaction is otherwise invalid, the VM will
+//! probably panic. If you want to handle untrusted transactions,
 //! you should use `SeqTransactionVM::new_untrusted`, which will not
 //! panic but instead return an error if the transaction is invalid.
 //!
@@ -140,6 +150,7 @@ extern crate block;
 extern crate log;
 
 mod commit;
+mod eof;
 pub mod errors;
 mod eval;
 mod memory;
@@ -150,15 +161,19 @@ mod stack;
 mod transaction;
 mod util;
 
-pub use crate::commit::{AccountChange, AccountCommitment, AccountState, BlockhashState, Storage};
-pub use crate::errors::{CommitError, NotSupportedError, OnChainError, PreExecutionError, RequireError};
+pub use crate::commit::{AccountChange, AccountCommitment, AccountState, AccountStateRead, BlockhashState, Storage};
+pub use crate::eof::{is_eof, EofContainer, FunctionType, EOF_MAGIC};
+pub use crate::errors::{CommitError, EofError, NotSupportedError, OnChainError, PreExecutionError, RequireError};
 pub use crate::eval::{Machine, MachineStatus, Runtime, State};
 pub use crate::memory::{Memory, SeqMemory};
 pub use crate::params::*;
 pub use crate::patch::*;
 pub use crate::pc::{Instruction, PCMut, Valids, PC};
 pub use crate::stack::Stack;
-pub use crate::transaction::{TransactionVM, UntrustedTransaction, ValidTransaction};
+pub use crate::transaction::{
+    AccessListItem, AccessListTransaction, TransactionVM, UntrustedTransaction, ValidTransaction,
+    ACCESS_LIST_TRANSACTION_TYPE, VERSIONED_HASH_VERSION_KZG,
+};
 pub use crate::util::opcode::Opcode;
 pub use block_core::TransactionAction;
 
@@ -265,12 +280,13 @@ pub struct ContextVM<'a, M, P: Patch> {
 impl<'a, M: Memory, P: Patch> ContextVM<'a, M, P> {
     /// Create a new VM using the given context, block header and patch.
     pub fn new(patch: &'a P, context: Context, block: HeaderParams) -> Self {
+        let runtime = Runtime::new(block);
         let mut machines = Vec::new();
         let account_patch = patch.account_patch();
-        machines.push(Machine::new(patch, context, 1));
+        machines.push(Machine::with_runtime(patch, context, &runtime, 1));
         ContextVM {
             machines,
-            runtime: Runtime::new(block),
+            runtime,
             fresh_account_state: AccountState::new(account_patch),
         }
     }
@@ -283,11 +299,18 @@ impl<'a, M: Memory, P: Patch> ContextVM<'a, M, P> {
         account_state: AccountState<'a, P::Account>,
         blockhash_state: BlockhashState,
     ) -> Self {
+        let runtime = Runtime::with_states(block, blockhash_state);
         let mut machines = Vec::new();
-        machines.push(Machine::with_states(patch, context, 1, account_state.clone()));
+        machines.push(Machine::with_runtime_states(
+            patch,
+            context,
+            &runtime,
+            1,
+            account_state.clone(),
+        ));
         ContextVM {
             machines,
-            runtime: Runtime::with_states(block, blockhash_state),
+            runtime,
             fresh_account_state: account_state,
         }
     }
@@ -477,7 +500,8 @@ impl<'a, M: Memory, P: Patch> VM for ContextVM<'a, M, P> {
 
     fn used_gas(&self) -> Gas {
         let total_used = self.machines[0].state().total_used_gas();
-        let refund_cap = total_used / Gas::from(2u64);
+        let refund_quotient = self.machines[0].state().patch.max_refund_quotient();
+        let refund_cap = total_used / Gas::from(refund_quotient.get());
         let refunded = min(refund_cap, self.machines[0].state().refunded_gas);
         total_used - refunded
     }
@@ -3,6 +3,10 @@
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use bigint::{M256, U256};
+#[cfg(not(feature = "std"))]
+use core::cmp::min;
+#[cfg(feature = "std")]
+use std::cmp::min;
 
 use super::errors::NotSupportedError;
 
@@ -28,6 +32,24 @@ pub trait Memory: Sized {
     fn read(&self, index: U256) -> M256;
     /// Read only one byte value from the index.
     fn read_raw(&self, index: U256) -> u8;
+
+    /// Copy `len` bytes starting at `src` to `dst`. The two ranges
+    /// may overlap, in which case the result is as if the source was
+    /// first copied into an intermediate buffer.
+    fn copy(&mut self, dst: U256, src: U256, len: U256) -> Result<(), NotSupportedError> {
+        self.check_write_range(dst, len)?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut i = U256::zero();
+        while i < len {
+            buffer.push(self.read_raw(src.saturating_add(i)));
+            i = i + U256::from(1u64);
+        }
+        for (i, value) in buffer.into_iter().enumerate() {
+            self.write_raw(dst + U256::from(i), value)?;
+        }
+        Ok(())
+    }
 }
 
 /// A sequencial memory. It uses Rust's `Vec` for internal
@@ -131,4 +153,81 @@ impl Memory for SeqMemory {
 
         self.memory[index]
     }
+
+    fn copy(&mut self, dst: U256, src: U256, len: U256) -> Result<(), NotSupportedError> {
+        if len == U256::zero() {
+            return Ok(());
+        }
+        self.check_write_range(dst, len)?;
+
+        let dst = dst.as_usize();
+        let len = len.as_usize();
+        if self.memory.len() < dst + len {
+            self.memory.resize(dst + len, 0u8);
+        }
+
+        // Bytes past the end of the memory read as zero.
+        let copied = if src < U256::from(self.memory.len()) {
+            let src = src.as_usize();
+            let copied = min(len, self.memory.len() - src);
+            self.memory.copy_within(src..(src + copied), dst);
+            copied
+        } else {
+            0
+        };
+        for value in &mut self.memory[(dst + copied)..(dst + len)] {
+            *value = 0u8;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Memory holding the bytes 0, 1, 2, ... up to `len`.
+    fn memory(len: u8) -> SeqMemory {
+        let mut memory = SeqMemory::new(usize::MAX);
+        for i in 0..len {
+            memory.write_raw(U256::from(i), i).unwrap();
+        }
+        memory
+    }
+
+    fn bytes(memory: &SeqMemory, len: usize) -> Vec<u8> {
+        (0..len).map(|i| memory.read_raw(U256::from(i))).collect()
+    }
+
+    #[test]
+    fn copy_overlapping_forward() {
+        let mut memory = memory(10);
+        memory.copy(U256::from(1u64), U256::zero(), U256::from(8u64)).unwrap();
+        assert_eq!(bytes(&memory, 10), vec![0, 0, 1, 2, 3, 4, 5, 6, 7, 9]);
+    }
+
+    #[test]
+    fn copy_overlapping_backward() {
+        let mut memory = memory(10);
+        memory.copy(U256::zero(), U256::from(1u64), U256::from(8u64)).unwrap();
+        assert_eq!(bytes(&memory, 10), vec![1, 2, 3, 4, 5, 6, 7, 8, 8, 9]);
+    }
+
+    #[test]
+    fn copy_past_the_end() {
+        let mut memory = memory(4);
+        memory
+            .copy(U256::from(2u64), U256::from(1u64), U256::from(6u64))
+            .unwrap();
+        assert_eq!(memory.len(), 8);
+        assert_eq!(bytes(&memory, 8), vec![0, 1, 1, 2, 3, 0, 0, 0]);
+    }
+
+    #[test]
+    fn copy_zero_length() {
+        let mut memory = memory(4);
+        memory.copy(U256::max_value(), U256::max_value(), U256::zero()).unwrap();
+        assert_eq!(memory.len(), 4);
+        assert_eq!(bytes(&memory, 4), vec![0, 1, 2, 3]);
+    }
 }
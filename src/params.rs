@@ -8,7 +8,9 @@ use alloc::rc::Rc;
 #[cfg(feature = "std")]
 use std::rc::Rc;
 
-use bigint::{Address, Gas, U256};
+use bigint::{Address, Gas, H256, U256};
+
+use crate::Patch;
 #[cfg(feature = "std")]
 use block::Header;
 
@@ -23,8 +25,50 @@ pub struct HeaderParams {
     pub number: U256,
     /// Difficulty of the block.
     pub difficulty: U256,
+    /// Randomness from the beacon chain, returned by the DIFFICULTY
+    /// opcode after the merge. (EIP4399)
+    pub prev_randao: H256,
     /// Total block gas limit.
     pub gas_limit: Gas,
+    /// Base fee per gas of the block. (EIP1559)
+    pub base_fee: Gas,
+    /// Excess blob gas of the block, from which the blob base fee is
+    /// derived. (EIP4844)
+    pub excess_blob_gas: Gas,
+}
+
+impl HeaderParams {
+    /// Fee per blob gas of the block, derived from the excess blob
+    /// gas. (EIP4844)
+    pub fn blob_base_fee<P: Patch>(&self, patch: &P) -> Gas {
+        fake_exponential(
+            U256::from(MIN_BLOB_BASE_FEE),
+            self.excess_blob_gas.into(),
+            U256::from(patch.blob_base_fee_update_fraction().get()),
+        )
+        .into()
+    }
+}
+
+/// Minimum fee per blob gas. (EIP4844)
+const MIN_BLOB_BASE_FEE: u64 = 1;
+
+/// Integer approximation of `factor * e ^ (numerator / denominator)`
+/// by its Taylor expansion, saturating on overflow. (EIP4844)
+fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    let mut output = U256::zero();
+    let mut accum = factor.saturating_mul(denominator);
+    let mut i = U256::one();
+    while accum > U256::zero() {
+        output = output.saturating_add(accum);
+        let (product, overflowed) = accum.overflowing_mul(numerator);
+        if overflowed {
+            return U256::max_value();
+        }
+        accum = product / denominator.saturating_mul(i);
+        i = i + U256::one();
+    }
+    output / denominator
 }
 
 #[cfg(feature = "std")]
@@ -35,7 +79,13 @@ impl<'a> From<&'a Header> for HeaderParams {
             timestamp: val.timestamp,
             number: val.number,
             difficulty: val.difficulty,
+            // After the merge the mix hash field holds the randomness
+            prev_randao: val.mix_hash,
             gas_limit: val.gas_limit,
+            // Headers before EIP1559 and EIP4844 carry no base fee
+            // or blob gas
+            base_fee: Gas::zero(),
+            excess_blob_gas: Gas::zero(),
         }
     }
 }
@@ -68,6 +118,65 @@ pub struct Context {
     pub is_system: bool,
     /// Whether this call is static.
     pub is_static: bool,
+    /// Versioned hashes of the blobs carried by the transaction.
+    /// (EIP4844)
+    pub blob_versioned_hashes: Rc<Vec<H256>>,
 }
 
 pub use block_core::Log;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fake_exponential_vectors() {
+        // Factor, numerator, denominator and result
+        let cases: &[(u64, u64, u64, u64)] = &[
+            (1, 0, 1, 1),
+            (38493, 0, 1000, 38493),
+            (0, 1234567890, 1, 0),
+            (1, 2, 1, 6),
+            (2, 2, 1, 13),
+            (1, 4, 2, 6),
+            (1, 3, 1, 16),
+            (1, 6, 2, 18),
+            (1, 4, 1, 49),
+            (1, 8, 2, 50),
+            (10, 8, 2, 542),
+            (11, 8, 2, 596),
+            (1, 5, 1, 136),
+            (1, 5, 2, 11),
+            (2, 5, 2, 23),
+            (1, 50000000, 2225652, 5709098764),
+        ];
+        for &(factor, numerator, denominator, result) in cases {
+            assert_eq!(
+                fake_exponential(factor.into(), numerator.into(), denominator.into()),
+                U256::from(result)
+            );
+        }
+        assert_eq!(
+            fake_exponential(U256::one(), U256::max_value(), U256::one()),
+            U256::max_value()
+        );
+    }
+    #[test]
+    fn blob_base_fee() {
+        let mut header = HeaderParams {
+            beneficiary: Address::default(),
+            timestamp: 0,
+            number: U256::zero(),
+            difficulty: U256::zero(),
+            prev_randao: H256::default(),
+            gas_limit: Gas::zero(),
+            base_fee: Gas::zero(),
+            excess_blob_gas: Gas::zero(),
+        };
+        let patch = crate::EmbeddedPatch::default();
+        assert_eq!(header.blob_base_fee(&patch), Gas::one());
+
+        header.excess_blob_gas = Gas::from(10_000_000u64);
+        assert_eq!(header.blob_base_fee(&patch), Gas::from(19u64));
+    }
+}
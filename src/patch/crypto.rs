@@ -0,0 +1,182 @@
+//! Hash functions and signature recovery, selectable by the patch.
+
+use bigint::{Address, H256};
+use digest::{Digest, FixedOutput};
+use ripemd160::Ripemd160;
+use sha2::Sha256;
+use sha3::Keccak256;
+
+#[cfg(feature = "rust-secp256k1")]
+use secp256k1::{recover, Message, RecoveryId, Signature};
+#[cfg(feature = "c-secp256k1")]
+use secp256k1::{Message, RecoverableSignature, RecoveryId, SECP256K1};
+
+use crate::errors::NotSupportedError;
+
+/// Provider of the hash functions and signature recovery used by the
+/// SHA3, CREATE2 and EXTCODEHASH opcodes, the ECREC, SHA256 and
+/// RIP160 precompiled contracts, contract address derivation and
+/// transaction sender recovery. Implementations may record every
+/// invocation or replace the native implementations.
+pub trait CryptoProvider {
+    /// Keccak-256 hash of the data.
+    fn keccak256(&self, data: &[u8]) -> H256;
+    /// SHA-256 hash of the data.
+    fn sha256(&self, data: &[u8]) -> H256;
+    /// RIPEMD-160 hash of the data.
+    fn ripemd160(&self, data: &[u8]) -> [u8; 20];
+    /// Recover the address that signed a message hash, given the
+    /// recovery ID (0 or 1) and the `r` and `s` values of the
+    /// signature. Returns `None` if the signature is not recoverable.
+    fn ecrecover(
+        &self,
+        hash: &H256,
+        recovery_id: u8,
+        signature: &[u8; 64],
+    ) -> Result<Option<Address>, NotSupportedError>;
+}
+
+/// Crypto provider using the `sha3`, `sha2` and `ripemd160` crates,
+/// and the secp256k1 library selected by the crate features.
+/// Signature recovery is not supported without one.
+#[derive(Default, Copy, Clone)]
+pub struct NativeCryptoProvider;
+
+/// Static value of the native crypto provider.
+pub static NATIVE_CRYPTO_PROVIDER: NativeCryptoProvider = NativeCryptoProvider;
+
+impl CryptoProvider for NativeCryptoProvider {
+    fn keccak256(&self, data: &[u8]) -> H256 {
+        H256::from(Keccak256::digest(data).as_slice())
+    }
+
+    fn sha256(&self, data: &[u8]) -> H256 {
+        H256::from(Sha256::digest(data).as_slice())
+    }
+
+    fn ripemd160(&self, data: &[u8]) -> [u8; 20] {
+        let mut ripemd = Ripemd160::default();
+        ripemd.input(data);
+        let mut result = [0u8; 20];
+        result.copy_from_slice(&ripemd.fixed_result());
+        result
+    }
+
+    #[cfg(any(feature = "c-secp256k1", feature = "rust-secp256k1"))]
+    fn ecrecover(
+        &self,
+        hash: &H256,
+        recovery_id: u8,
+        signature: &[u8; 64],
+    ) -> Result<Option<Address>, NotSupportedError> {
+        Ok(secp256k1_recover(hash, recovery_id, signature)
+            .map(|key| Address::from(&Keccak256::digest(&key[..]).as_slice()[12..])))
+    }
+
+    #[cfg(all(not(feature = "c-secp256k1"), not(feature = "rust-secp256k1")))]
+    fn ecrecover(&self, _: &H256, _: u8, _: &[u8; 64]) -> Result<Option<Address>, NotSupportedError> {
+        Err(NotSupportedError::PrecompiledNotSupported)
+    }
+}
+
+/// Uncompressed public key, without its prefix, that signed the hash.
+#[cfg(feature = "c-secp256k1")]
+fn secp256k1_recover(hash: &H256, recovery_id: u8, signature: &[u8; 64]) -> Option<[u8; 64]> {
+    let message = Message::from_slice(&hash[..]).ok()?;
+    let recovery_id = RecoveryId::from_i32(i32::from(recovery_id)).ok()?;
+    let signature = RecoverableSignature::from_compact(&SECP256K1, &signature[..], recovery_id).ok()?;
+    let recovered = SECP256K1.recover(&message, &signature).ok()?;
+    let key = recovered.serialize_vec(&SECP256K1, false);
+
+    let mut ret = [0u8; 64];
+    ret.copy_from_slice(&key[1..65]);
+    Some(ret)
+}
+
+/// Uncompressed public key, without its prefix, that signed the hash.
+#[cfg(feature = "rust-secp256k1")]
+fn secp256k1_recover(hash: &H256, recovery_id: u8, signature: &[u8; 64]) -> Option<[u8; 64]> {
+    let mut message_raw = [0u8; 32];
+    message_raw.copy_from_slice(&hash[..]);
+    let message = Message::parse(&message_raw);
+    let recovery_id = RecoveryId::parse(recovery_id).ok()?;
+    let signature = Signature::parse(signature);
+    let recovered = recover(&message, &signature, &recovery_id).ok()?;
+    let key = recovered.serialize();
+
+    let mut ret = [0u8; 64];
+    ret.copy_from_slice(&key[1..65]);
+    Some(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patch::{call_precompiled, ECREC_PRECOMPILED, SHA256_PRECOMPILED};
+    use bigint::Gas;
+    use hexutil::read_hex;
+    use std::cell::RefCell;
+
+    /// Provider recording the name of every invoked function, with
+    /// signature recovery replaced by a fixed address.
+    #[derive(Default)]
+    struct RecordingCryptoProvider(RefCell<Vec<&'static str>>);
+
+    impl CryptoProvider for RecordingCryptoProvider {
+        fn keccak256(&self, data: &[u8]) -> H256 {
+            self.0.borrow_mut().push("keccak256");
+            NATIVE_CRYPTO_PROVIDER.keccak256(data)
+        }
+        fn sha256(&self, data: &[u8]) -> H256 {
+            self.0.borrow_mut().push("sha256");
+            NATIVE_CRYPTO_PROVIDER.sha256(data)
+        }
+        fn ripemd160(&self, data: &[u8]) -> [u8; 20] {
+            self.0.borrow_mut().push("ripemd160");
+            NATIVE_CRYPTO_PROVIDER.ripemd160(data)
+        }
+        fn ecrecover(&self, _: &H256, recovery_id: u8, _: &[u8; 64]) -> Result<Option<Address>, NotSupportedError> {
+            self.0.borrow_mut().push("ecrecover");
+            assert_eq!(recovery_id, 1);
+            Ok(Some(Address::from(0x1234)))
+        }
+    }
+
+    #[test]
+    fn native_hashes() {
+        assert_eq!(
+            NATIVE_CRYPTO_PROVIDER.keccak256(&[]),
+            H256::from("0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            NATIVE_CRYPTO_PROVIDER.sha256(&[]),
+            H256::from("0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        assert_eq!(
+            NATIVE_CRYPTO_PROVIDER.ripemd160(&[])[..],
+            read_hex("0x9c1185a5c5e9fc54612808977ee8f548b2258d31").unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn precompiled_with_provider() {
+        let input = read_hex("0x456e9aea5e197a1f1af7a3e85a3212fa4049a3ba34c2289b4c860fc0b0c64ef3000000000000000000000000000000000000000000000000000000000000001c9242685bf161793cc25603c231bc2f568eb630ea16aa137d2664ac80388256084f8ae3bd7535248d0bd448298cc2e2071e56992d0774dc340c368ae950852ada").unwrap();
+        let crypto = RecordingCryptoProvider::default();
+
+        let (_, output) = call_precompiled(&ECREC_PRECOMPILED, &input, Gas::from(3000usize), &crypto).unwrap();
+        assert_eq!(
+            output[..],
+            read_hex("0x0000000000000000000000000000000000000000000000000000000000001234").unwrap()[..]
+        );
+
+        let (_, output) = call_precompiled(&SHA256_PRECOMPILED, &[], Gas::from(60usize), &crypto).unwrap();
+        assert_eq!(
+            output,
+            call_precompiled(&SHA256_PRECOMPILED, &[], Gas::from(60usize), &NATIVE_CRYPTO_PROVIDER)
+                .unwrap()
+                .1
+        );
+
+        assert_eq!(*crypto.0.borrow(), vec!["ecrecover", "sha256"]);
+    }
+}
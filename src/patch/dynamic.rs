@@ -1,7 +1,16 @@
+#[cfg(not(feature = "std"))]
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use core::num::{NonZeroU64, NonZeroUsize};
+
 use bigint::{Address, Gas, U256};
 use smallvec::SmallVec;
 
-use crate::patch::{AccountPatch, Patch, Precompiled};
+use crate::patch::{AccountPatch, CryptoProvider, Patch, Precompiled, PrecompiledRegistry};
 
 #[derive(Copy, Clone)]
 /// AccountPatch that can be configured in client code runtime
@@ -48,6 +57,8 @@ pub struct DynamicPatch {
     pub gas_extcode: Gas,
     /// Gas paid for BALANCE opcode.
     pub gas_balance: Gas,
+    /// Gas paid for EXTCODEHASH opcode.
+    pub gas_extcodehash: Gas,
     /// Gas paid for SLOAD opcode.
     pub gas_sload: Gas,
     /// Gas paid for SUICIDE opcode.
@@ -79,6 +90,61 @@ pub struct DynamicPatch {
     pub has_create2: bool,
     /// Whether EVM should implement the EIP1283 gas metering scheme for SSTORE opcode
     pub has_reduced_sstore_gas_metering: bool,
+    /// Whether EVM should implement the EIP2200 net gas metering scheme for SSTORE opcode
+    pub has_net_sstore_gas_metering: bool,
+    /// Whether accounts and storage slots are priced by warm and cold access. (EIP2929)
+    pub has_warm_cold_access: bool,
+    /// Gas paid for accessing an account or storage slot already accessed in this transaction.
+    pub gas_warm_access: Gas,
+    /// Gas paid for accessing an account not yet accessed in this transaction.
+    pub gas_cold_account_access: Gas,
+    /// Gas paid for accessing a storage slot not yet accessed in this transaction.
+    pub gas_cold_sload: Gas,
+    /// Whether the EVM has CHAINID opcode. (EIP1344)
+    pub has_chain_id: bool,
+    /// Chain ID returned by the CHAINID opcode.
+    pub chain_id: U256,
+    /// Whether the EVM has SELFBALANCE opcode. (EIP1884)
+    pub has_self_balance: bool,
+    /// Whether the EVM has BASEFEE opcode, and burns the base fee
+    /// portion of the transaction fee. (EIP1559/EIP3198)
+    pub has_base_fee: bool,
+    /// Maximum refund is the used gas divided by this quotient. (EIP3529)
+    pub max_refund_quotient: NonZeroUsize,
+    /// Gas refunded when a storage slot is cleared. (EIP3529)
+    pub refund_sstore_clears: Gas,
+    /// Gas refunded for SUICIDE opcode. (EIP3529)
+    pub refund_suicide: Gas,
+    /// Whether the EVM has PUSH0 opcode. (EIP3855)
+    pub has_push0: bool,
+    /// Maximum size of the init code of a contract creation. (EIP3860)
+    pub initcode_limit: Option<usize>,
+    /// Gas paid for every word of the init code of a contract
+    /// creation. (EIP3860)
+    pub gas_initcode_word: Gas,
+    /// Whether the EVM has TLOAD and TSTORE opcodes. (EIP1153)
+    pub has_transient_storage: bool,
+    /// Whether the EVM has MCOPY opcode. (EIP5656)
+    pub has_mcopy: bool,
+    /// Whether SUICIDE only deletes accounts created in the same
+    /// transaction, and otherwise only sends the balance. (EIP6780)
+    pub has_restricted_suicide: bool,
+    /// Whether new contract code starting with the 0xEF byte is
+    /// rejected. (EIP3541)
+    pub has_ef_code_rejection: bool,
+    /// Whether the EVM supports blob transactions and has BLOBHASH
+    /// and BLOBBASEFEE opcodes. (EIP4844, EIP7516)
+    pub has_blobs: bool,
+    /// Divisor of the excess blob gas in the exponential deriving the
+    /// blob base fee. (EIP4844, EIP7691)
+    pub blob_base_fee_update_fraction: NonZeroU64,
+    /// Whether the DIFFICULTY opcode returns the beacon chain
+    /// randomness instead of the block difficulty. (EIP4399)
+    pub has_prev_randao: bool,
+    /// Whether code deposits in the EOF format are accepted after
+    /// passing container validation. (EIP3540, EIP3670, EIP4200,
+    /// EIP4750, EIP5450)
+    pub has_eof: bool,
     /// Whether to throw out of gas error when
     /// CALL/CALLCODE/DELEGATECALL requires more than maximum amount
     /// of gas.
@@ -91,9 +157,15 @@ pub struct DynamicPatch {
     pub memory_limit: usize,
     /// Array of addresses of enabled precompiled contracts
     pub enabled_precompileds: SmallVec<[Address; 8]>,
-    /// Precompiled contracts at given address, with required code,
-    /// and its definition.
-    pub precompileds: &'static [(Address, Option<&'static [u8]>, &'static dyn Precompiled)],
+    /// Registry of the precompiled contracts at given address, with
+    /// required code, its definition and activation block. Only the
+    /// ones in `enabled_precompileds` can be called, from their
+    /// activation block on.
+    pub precompileds: Arc<PrecompiledRegistry>,
+    /// Provider of the hash functions and signature recovery used by
+    /// the SHA3, CREATE2 and EXTCODEHASH opcodes and the precompiled
+    /// contracts.
+    pub crypto: Arc<dyn CryptoProvider + Send + Sync>,
 }
 
 #[rustfmt::skip]
@@ -104,6 +176,7 @@ impl Patch for DynamicPatch {
     fn callstack_limit(&self) -> usize { self.callstack_limit }
     fn gas_extcode(&self) -> Gas { self.gas_extcode }
     fn gas_balance(&self) -> Gas { self.gas_balance }
+    fn gas_extcodehash(&self) -> Gas { self.gas_extcodehash }
     fn gas_sload(&self) -> Gas { self.gas_sload }
     fn gas_suicide(&self) -> Gas { self.gas_suicide }
     fn gas_suicide_new_account(&self) -> Gas { self.gas_suicide_new_account }
@@ -119,13 +192,51 @@ impl Patch for DynamicPatch {
     fn has_create2(&self) -> bool { self.has_create2 }
     fn has_extcodehash(&self) -> bool { self.has_extcodehash }
     fn has_reduced_sstore_gas_metering(&self) -> bool { self.has_reduced_sstore_gas_metering }
+    fn has_net_sstore_gas_metering(&self) -> bool { self.has_net_sstore_gas_metering }
+    fn has_warm_cold_access(&self) -> bool { self.has_warm_cold_access }
+    fn gas_warm_access(&self) -> Gas { self.gas_warm_access }
+    fn gas_cold_account_access(&self) -> Gas { self.gas_cold_account_access }
+    fn gas_cold_sload(&self) -> Gas { self.gas_cold_sload }
+    fn has_chain_id(&self) -> bool { self.has_chain_id }
+    fn chain_id(&self) -> U256 { self.chain_id }
+    fn has_self_balance(&self) -> bool { self.has_self_balance }
+    fn has_base_fee(&self) -> bool { self.has_base_fee }
+    fn max_refund_quotient(&self) -> NonZeroUsize { self.max_refund_quotient }
+    fn refund_sstore_clears(&self) -> Gas { self.refund_sstore_clears }
+    fn refund_suicide(&self) -> Gas { self.refund_suicide }
+    fn has_push0(&self) -> bool { self.has_push0 }
+    fn initcode_limit(&self) -> Option<usize> { self.initcode_limit }
+    fn gas_initcode_word(&self) -> Gas { self.gas_initcode_word }
+    fn has_transient_storage(&self) -> bool { self.has_transient_storage }
+    fn has_mcopy(&self) -> bool { self.has_mcopy }
+    fn has_restricted_suicide(&self) -> bool { self.has_restricted_suicide }
+    fn has_ef_code_rejection(&self) -> bool { self.has_ef_code_rejection }
+    fn has_blobs(&self) -> bool { self.has_blobs }
+    fn blob_base_fee_update_fraction(&self) -> NonZeroU64 { self.blob_base_fee_update_fraction }
+    fn has_prev_randao(&self) -> bool { self.has_prev_randao }
+    fn has_eof(&self) -> bool { self.has_eof }
     fn err_on_call_with_more_gas(&self) -> bool { self.err_on_call_with_more_gas }
     fn call_create_l64_after_gas(&self) -> bool { self.call_create_l64_after_gas }
     fn memory_limit(&self) -> usize { self.memory_limit }
     fn is_precompiled_contract_enabled(&self, address: &Address) -> bool {
         self.enabled_precompileds.iter().find(|&a| a == address).is_some()
     }
-    fn precompileds(&self) -> &[(Address, Option<&'static [u8]>, &'static dyn Precompiled)] {
-        &self.precompileds
+    fn precompileds(&self) -> &[(Address, Option<&[u8]>, &dyn Precompiled)] { &[] }
+    fn precompiled(&self, address: &Address, code: &[u8], number: U256) -> Option<&dyn Precompiled> {
+        if !self.is_precompiled_contract_enabled(address) {
+            return None;
+        }
+        self.precompileds.find(address, code, number)
+    }
+    fn precompiled_addresses(&self, number: U256) -> Vec<Address> {
+        self.enabled_precompileds
+            .iter()
+            .filter(|address| match self.precompileds.get(address) {
+                Some(entry) => entry.activation_block <= number,
+                None => false,
+            })
+            .cloned()
+            .collect()
     }
+    fn crypto(&self) -> &dyn CryptoProvider { &*self.crypto }
 }
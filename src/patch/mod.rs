@@ -1,11 +1,20 @@
 //! Patch of a VM, indicating different hard-fork of the Ethereum
 //! block range.
 
+mod crypto;
 mod dynamic;
 mod precompiled;
+mod registry;
 
+pub use self::crypto::*;
 pub use self::dynamic::*;
 pub use self::precompiled::*;
+pub use self::registry::*;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::num::{NonZeroU64, NonZeroUsize};
 
 use bigint::{Address, Gas, H160, U256};
 
@@ -50,6 +59,10 @@ pub trait Patch {
     fn gas_extcode(&self) -> Gas;
     /// Gas paid for BALANCE opcode.
     fn gas_balance(&self) -> Gas;
+    /// Gas paid for EXTCODEHASH opcode.
+    fn gas_extcodehash(&self) -> Gas {
+        Gas::from(400usize)
+    }
     /// Gas paid for SLOAD opcode.
     fn gas_sload(&self) -> Gas;
     /// Gas paid for SUICIDE opcode.
@@ -81,6 +94,108 @@ pub trait Patch {
     fn has_extcodehash(&self) -> bool;
     /// Whether EVM should implement the EIP1283 gas metering scheme for SSTORE opcode
     fn has_reduced_sstore_gas_metering(&self) -> bool;
+    /// Whether EVM should implement the EIP2200 net gas metering scheme for SSTORE opcode,
+    /// including the gas stipend sentry. Takes precedence over EIP1283.
+    fn has_net_sstore_gas_metering(&self) -> bool {
+        false
+    }
+    /// Whether accounts and storage slots are priced by warm and cold access. (EIP2929)
+    fn has_warm_cold_access(&self) -> bool {
+        false
+    }
+    /// Gas paid for accessing an account or storage slot already accessed in this transaction.
+    fn gas_warm_access(&self) -> Gas {
+        Gas::from(100usize)
+    }
+    /// Gas paid for accessing an account not yet accessed in this transaction.
+    fn gas_cold_account_access(&self) -> Gas {
+        Gas::from(2600usize)
+    }
+    /// Gas paid for accessing a storage slot not yet accessed in this transaction.
+    fn gas_cold_sload(&self) -> Gas {
+        Gas::from(2100usize)
+    }
+    /// Whether the EVM has CHAINID opcode. (EIP1344)
+    fn has_chain_id(&self) -> bool {
+        false
+    }
+    /// Chain ID returned by the CHAINID opcode.
+    fn chain_id(&self) -> U256 {
+        U256::zero()
+    }
+    /// Whether the EVM has SELFBALANCE opcode. (EIP1884)
+    fn has_self_balance(&self) -> bool {
+        false
+    }
+    /// Whether the EVM has BASEFEE opcode, and burns the base fee
+    /// portion of the transaction fee. (EIP1559/EIP3198)
+    fn has_base_fee(&self) -> bool {
+        false
+    }
+    /// Maximum refund is the used gas divided by this quotient. (EIP3529)
+    fn max_refund_quotient(&self) -> NonZeroUsize {
+        NonZeroUsize::new(2).unwrap()
+    }
+    /// Gas refunded when a storage slot is cleared. (EIP3529)
+    fn refund_sstore_clears(&self) -> Gas {
+        Gas::from(15000usize)
+    }
+    /// Gas refunded for SUICIDE opcode. (EIP3529)
+    fn refund_suicide(&self) -> Gas {
+        Gas::from(24000usize)
+    }
+    /// Whether the EVM has PUSH0 opcode. (EIP3855)
+    fn has_push0(&self) -> bool {
+        false
+    }
+    /// Maximum size of the init code of a contract creation. (EIP3860)
+    fn initcode_limit(&self) -> Option<usize> {
+        None
+    }
+    /// Gas paid for every word of the init code of a contract
+    /// creation, only charged when `initcode_limit` is set. (EIP3860)
+    fn gas_initcode_word(&self) -> Gas {
+        Gas::zero()
+    }
+    /// Whether the EVM has TLOAD and TSTORE opcodes. (EIP1153)
+    fn has_transient_storage(&self) -> bool {
+        false
+    }
+    /// Whether the EVM has MCOPY opcode. (EIP5656)
+    fn has_mcopy(&self) -> bool {
+        false
+    }
+    /// Whether SUICIDE only deletes accounts created in the same
+    /// transaction, and otherwise only sends the balance. (EIP6780)
+    fn has_restricted_suicide(&self) -> bool {
+        false
+    }
+    /// Whether new contract code starting with the 0xEF byte is
+    /// rejected. (EIP3541)
+    fn has_ef_code_rejection(&self) -> bool {
+        false
+    }
+    /// Whether the EVM supports blob transactions and has BLOBHASH
+    /// and BLOBBASEFEE opcodes. (EIP4844, EIP7516)
+    fn has_blobs(&self) -> bool {
+        false
+    }
+    /// Divisor of the excess blob gas in the exponential deriving the
+    /// blob base fee. (EIP4844, EIP7691)
+    fn blob_base_fee_update_fraction(&self) -> NonZeroU64 {
+        NonZeroU64::new(3_338_477).unwrap()
+    }
+    /// Whether the DIFFICULTY opcode returns the beacon chain
+    /// randomness instead of the block difficulty. (EIP4399)
+    fn has_prev_randao(&self) -> bool {
+        false
+    }
+    /// Whether code deposits in the EOF format are accepted after
+    /// passing container validation. (EIP3540, EIP3670, EIP4200,
+    /// EIP4750, EIP5450)
+    fn has_eof(&self) -> bool {
+        false
+    }
     /// Whether to throw out of gas error when
     /// CALL/CALLCODE/DELEGATECALL requires more than maximum amount
     /// of gas.
@@ -94,8 +209,41 @@ pub trait Patch {
     /// Check if the precompiled contract enabled
     fn is_precompiled_contract_enabled(&self, address: &Address) -> bool;
     /// Precompiled contracts at given address, with required code,
-    /// and its definition.
+    /// and its definition. Only the backing table of the default
+    /// `precompiled` and `precompiled_addresses`, so patches
+    /// overriding both can return an empty one.
+    #[deprecated(note = "look precompiled contracts up with `precompiled` and `precompiled_addresses`")]
     fn precompileds(&self) -> &[(Address, Option<&[u8]>, &dyn Precompiled)];
+    /// Precompiled contract enabled at given address in the block of
+    /// the given number, if the code at the address matches the
+    /// required one. Defaults to a search in `precompileds`.
+    #[allow(deprecated)]
+    fn precompiled(&self, address: &Address, code: &[u8], _number: U256) -> Option<&dyn Precompiled> {
+        if !self.is_precompiled_contract_enabled(address) {
+            return None;
+        }
+        self.precompileds()
+            .iter()
+            .find(|precompiled| precompiled.0 == *address && (precompiled.1.is_none() || precompiled.1 == Some(code)))
+            .map(|precompiled| precompiled.2)
+    }
+    /// Addresses of all precompiled contracts enabled in the block of
+    /// the given number. Defaults to the enabled ones in
+    /// `precompileds`.
+    #[allow(deprecated)]
+    fn precompiled_addresses(&self, _number: U256) -> Vec<Address> {
+        self.precompileds()
+            .iter()
+            .map(|precompiled| precompiled.0)
+            .filter(|address| self.is_precompiled_contract_enabled(address))
+            .collect()
+    }
+    /// Provider of the hash functions and signature recovery used by
+    /// the SHA3, CREATE2 and EXTCODEHASH opcodes and the precompiled
+    /// contracts. Defaults to the native implementations.
+    fn crypto(&self) -> &dyn CryptoProvider {
+        &NATIVE_CRYPTO_PROVIDER
+    }
 }
 
 /// Default precompiled collections.
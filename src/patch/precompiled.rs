@@ -6,55 +6,102 @@ use alloc::rc::Rc;
 #[cfg(feature = "std")]
 use std::rc::Rc;
 
-use bigint::Gas;
-#[cfg(all(not(feature = "std"), any(feature = "rust-secp256k1", feature = "c-secp256k1")))]
+use bigint::{Address, Gas, H256, M256, U256};
+#[cfg(not(feature = "std"))]
 use core::cmp::min;
-#[cfg(all(feature = "std", any(feature = "rust-secp256k1", feature = "c-secp256k1")))]
+#[cfg(feature = "std")]
 use std::cmp::min;
 
-use digest::{Digest, FixedOutput};
-use ripemd160::Ripemd160;
-use sha2::Sha256;
-#[cfg(any(feature = "rust-secp256k1", feature = "c-secp256k1"))]
-use sha3::Keccak256;
-
-#[cfg(feature = "rust-secp256k1")]
-use secp256k1::{recover, Error, Message, RecoveryId, Signature};
-#[cfg(feature = "c-secp256k1")]
-use secp256k1::{Error, Message, RecoverableSignature, RecoveryId, SECP256K1};
-
-use crate::errors::{OnChainError, RuntimeError};
+use crate::errors::{EvalError, OnChainError, RequireError, RuntimeError};
+use crate::patch::{CryptoProvider, NATIVE_CRYPTO_PROVIDER};
+use crate::{AccountStateRead, Context};
 
 /// Represent a precompiled contract.
 pub trait Precompiled: Sync {
-    /// Step a precompiled contract based on the gas required.
-    fn step(&self, _: &[u8]) -> Rc<Vec<u8>> {
-        unimplemented!()
+    /// Gas needed and output of a precompiled contract, given the gas
+    /// limit. An empty output is still a successful call, while an
+    /// error, such as `OnChainError::EmptyGas` or
+    /// `OnChainError::PrecompiledFailure`, fails the call.
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError>;
+    /// Gas needed and output of a call to the precompiled contract,
+    /// given the calling context, whose call data and gas limit are
+    /// the input, read access to the account state and the crypto
+    /// provider of the patch. This is what the VM calls. Returning
+    /// `EvalError::Require` asks for the account to be committed,
+    /// after which the call is retried. Defaults to `gas_and_step`.
+    fn gas_and_step_with_context(
+        &self,
+        context: &Context,
+        _state: &dyn AccountStateRead,
+        _crypto: &dyn CryptoProvider,
+    ) -> Result<(Gas, Rc<Vec<u8>>), EvalError> {
+        self.gas_and_step(&context.data, context.gas_limit)
+            .map_err(EvalError::from)
     }
-    /// Gas needed for a given computation.
-    fn gas(&self, _: &[u8]) -> Gas {
-        unimplemented!()
-    }
-    /// Combine step and gas together, given the gas limit.
+}
+
+impl<T: Precompiled + ?Sized> Precompiled for &T {
     fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
-        let gas = self.gas(data);
-        if gas > gas_limit {
-            Err(RuntimeError::OnChain(OnChainError::EmptyGas))
-        } else {
-            Ok((gas, self.step(data)))
-        }
+        (**self).gas_and_step(data, gas_limit)
+    }
+    fn gas_and_step_with_context(
+        &self,
+        context: &Context,
+        state: &dyn AccountStateRead,
+        crypto: &dyn CryptoProvider,
+    ) -> Result<(Gas, Rc<Vec<u8>>), EvalError> {
+        (**self).gas_and_step_with_context(context, state, crypto)
     }
 }
 
+/// Account state without any committed account, requiring every
+/// account it is asked for.
+struct NoAccountState;
+
+#[rustfmt::skip]
+impl AccountStateRead for NoAccountState {
+    fn code(&self, address: Address) -> Result<Rc<Vec<u8>>, RequireError> { Err(RequireError::AccountCode(address)) }
+    fn nonce(&self, address: Address) -> Result<U256, RequireError> { Err(RequireError::Account(address)) }
+    fn balance(&self, address: Address) -> Result<U256, RequireError> { Err(RequireError::Account(address)) }
+    fn storage_read(&self, address: Address, index: U256) -> Result<M256, RequireError> {
+        Err(RequireError::AccountStorage(address, index))
+    }
+}
+
+/// Call a precompiled contract outside of the VM with the given call
+/// data and gas limit, from and to the zero address, without any
+/// committed account.
+pub fn call_precompiled(
+    precompiled: &dyn Precompiled,
+    data: &[u8],
+    gas_limit: Gas,
+    crypto: &dyn CryptoProvider,
+) -> Result<(Gas, Rc<Vec<u8>>), EvalError> {
+    let context = Context {
+        address: Address::default(),
+        caller: Address::default(),
+        callee: Address::default(),
+        code: Rc::new(Vec::new()),
+        data: Rc::new(data.into()),
+        gas_limit,
+        gas_price: Gas::zero(),
+        origin: Address::default(),
+        value: U256::zero(),
+        apprent_value: U256::zero(),
+        is_system: false,
+        is_static: false,
+        blob_versioned_hashes: Rc::new(Vec::new()),
+    };
+    precompiled.gas_and_step_with_context(&context, &NoAccountState, crypto)
+}
+
 /// ID precompiled contract.
 pub struct IDPrecompiled;
 impl Precompiled for IDPrecompiled {
-    fn gas(&self, data: &[u8]) -> Gas {
-        Gas::from(15u64) + Gas::from(3u64) * gas_div_ceil(Gas::from(data.len()), Gas::from(32u64))
-    }
-
-    fn step(&self, data: &[u8]) -> Rc<Vec<u8>> {
-        Rc::new(data.into())
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        let gas = Gas::from(15u64) + Gas::from(3u64) * gas_div_ceil(Gas::from(data.len()), Gas::from(32u64));
+        let gas = charge(gas, gas_limit)?;
+        Ok((gas, Rc::new(data.into())))
     }
 }
 /// Static value of ID precompiled contract.
@@ -63,78 +110,107 @@ pub static ID_PRECOMPILED: IDPrecompiled = IDPrecompiled;
 /// RIP160 precompiled contract.
 pub struct RIP160Precompiled;
 impl Precompiled for RIP160Precompiled {
-    fn gas(&self, data: &[u8]) -> Gas {
-        Gas::from(600u64) + Gas::from(120u64) * gas_div_ceil(Gas::from(data.len()), Gas::from(32u64))
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        rip160(data, gas_limit, &NATIVE_CRYPTO_PROVIDER)
     }
 
-    fn step(&self, data: &[u8]) -> Rc<Vec<u8>> {
-        let mut ripemd = Ripemd160::default();
-        ripemd.input(data);
-        let fixed = ripemd.fixed_result();
-        let mut result: [u8; 32] = [0u8; 32];
-        for i in 0..20 {
-            result[i + 12] = fixed[i];
-        }
-        Rc::new(result.as_ref().into())
+    fn gas_and_step_with_context(
+        &self,
+        context: &Context,
+        _: &dyn AccountStateRead,
+        crypto: &dyn CryptoProvider,
+    ) -> Result<(Gas, Rc<Vec<u8>>), EvalError> {
+        Ok(rip160(&context.data, context.gas_limit, crypto)?)
     }
 }
 /// Static value of RIP160 precompiled contract.
 pub static RIP160_PRECOMPILED: RIP160Precompiled = RIP160Precompiled;
 
+fn rip160(data: &[u8], gas_limit: Gas, crypto: &dyn CryptoProvider) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+    let gas = Gas::from(600u64) + Gas::from(120u64) * gas_div_ceil(Gas::from(data.len()), Gas::from(32u64));
+    let gas = charge(gas, gas_limit)?;
+    let mut result = [0u8; 32];
+    result[12..].copy_from_slice(&crypto.ripemd160(data));
+    Ok((gas, Rc::new(result.as_ref().into())))
+}
+
 /// SHA256 precompiled contract.
 pub struct SHA256Precompiled;
 impl Precompiled for SHA256Precompiled {
-    fn gas(&self, data: &[u8]) -> Gas {
-        Gas::from(60u64) + Gas::from(12u64) * gas_div_ceil(Gas::from(data.len()), Gas::from(32u64))
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        sha256(data, gas_limit, &NATIVE_CRYPTO_PROVIDER)
     }
 
-    fn step(&self, data: &[u8]) -> Rc<Vec<u8>> {
-        let mut sha2 = Sha256::default();
-        sha2.input(data);
-        let fixed = sha2.fixed_result();
-        let mut result: [u8; 32] = [0u8; 32];
-        for i in 0..32 {
-            result[i] = fixed[i];
-        }
-        Rc::new(result.as_ref().into())
+    fn gas_and_step_with_context(
+        &self,
+        context: &Context,
+        _: &dyn AccountStateRead,
+        crypto: &dyn CryptoProvider,
+    ) -> Result<(Gas, Rc<Vec<u8>>), EvalError> {
+        Ok(sha256(&context.data, context.gas_limit, crypto)?)
     }
 }
 /// Static value of SHA256 precompiled contract.
 pub static SHA256_PRECOMPILED: SHA256Precompiled = SHA256Precompiled;
 
-/// ECREC precompiled contract.
+fn sha256(data: &[u8], gas_limit: Gas, crypto: &dyn CryptoProvider) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+    let gas = Gas::from(60u64) + Gas::from(12u64) * gas_div_ceil(Gas::from(data.len()), Gas::from(32u64));
+    let gas = charge(gas, gas_limit)?;
+    Ok((gas, Rc::new(crypto.sha256(data).as_ref().into())))
+}
+
+/// ECREC precompiled contract. Signature recovery is done by the
+/// crypto provider, and is not supported by the native one without a
+/// secp256k1 library.
 pub struct ECRECPrecompiled;
-#[cfg(any(feature = "c-secp256k1", feature = "rust-secp256k1"))]
 impl Precompiled for ECRECPrecompiled {
-    fn gas(&self, _: &[u8]) -> Gas {
-        Gas::from(3000u64)
+    fn gas_and_step(&self, data: &[u8], gas_limit: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+        ecrec(data, gas_limit, &NATIVE_CRYPTO_PROVIDER)
     }
 
-    fn step(&self, datao: &[u8]) -> Rc<Vec<u8>> {
-        let mut data = [0u8; 128];
-        let copy_bytes = min(datao.len(), 128);
-        data[..copy_bytes].clone_from_slice(&datao[..copy_bytes]);
-        match kececrec(&data) {
-            Ok(mut ret) => {
-                for i in &mut ret[..12] {
-                    *i = 0
-                }
-                Rc::new(ret.as_ref().into())
-            }
-            Err(_) => Rc::new(Vec::new()),
+    fn gas_and_step_with_context(
+        &self,
+        context: &Context,
+        _: &dyn AccountStateRead,
+        crypto: &dyn CryptoProvider,
+    ) -> Result<(Gas, Rc<Vec<u8>>), EvalError> {
+        Ok(ecrec(&context.data, context.gas_limit, crypto)?)
+    }
+}
+/// Static value of ECREC precompiled contract.
+pub static ECREC_PRECOMPILED: ECRECPrecompiled = ECRECPrecompiled;
+
+fn ecrec(datao: &[u8], gas_limit: Gas, crypto: &dyn CryptoProvider) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
+    let gas = charge(Gas::from(3000u64), gas_limit)?;
+
+    let mut data = [0u8; 128];
+    let copy_bytes = min(datao.len(), 128);
+    data[..copy_bytes].clone_from_slice(&datao[..copy_bytes]);
+
+    // Unrecoverable signatures are not a failure of the call.
+    let recovery_id = match data[63] {
+        27 | 28 if data[32..63] == [0; 31] => data[63] - 27,
+        _ => return Ok((gas, Rc::new(Vec::new()))),
+    };
+    let mut signature = [0u8; 64];
+    signature.copy_from_slice(&data[64..128]);
+    match crypto.ecrecover(&H256::from(&data[0..32]), recovery_id, &signature)? {
+        Some(address) => {
+            let mut ret = [0u8; 32];
+            ret[12..].copy_from_slice(&address[..]);
+            Ok((gas, Rc::new(ret.as_ref().into())))
         }
+        None => Ok((gas, Rc::new(Vec::new()))),
     }
 }
-#[cfg(all(not(feature = "c-secp256k1"), not(feature = "rust-secp256k1")))]
-impl Precompiled for ECRECPrecompiled {
-    fn gas_and_step(&self, _: &[u8], _: Gas) -> Result<(Gas, Rc<Vec<u8>>), RuntimeError> {
-        use crate::errors::NotSupportedError;
 
-        Err(RuntimeError::NotSupported(NotSupportedError::PrecompiledNotSupported))
+fn charge(gas: Gas, gas_limit: Gas) -> Result<Gas, RuntimeError> {
+    if gas > gas_limit {
+        Err(RuntimeError::OnChain(OnChainError::EmptyGas))
+    } else {
+        Ok(gas)
     }
 }
-/// Static value of ECREC precompiled contract.
-pub static ECREC_PRECOMPILED: ECRECPrecompiled = ECRECPrecompiled;
 
 fn gas_div_ceil(a: Gas, b: Gas) -> Gas {
     if a % b == Gas::zero() {
@@ -143,49 +219,3 @@ fn gas_div_ceil(a: Gas, b: Gas) -> Gas {
         a / b + Gas::from(1u64)
     }
 }
-
-#[cfg(feature = "c-secp256k1")]
-fn kececrec(data: &[u8]) -> Result<[u8; 32], Error> {
-    let message = Message::from_slice(&data[0..32])?;
-    let recid_raw = match data[63] {
-        27 | 28 if data[32..63] == [0; 31] => data[63] - 27,
-        _ => return Err(Error::InvalidRecoveryId),
-    };
-    let recid = RecoveryId::from_i32(i32::from(recid_raw))?;
-    let sig = RecoverableSignature::from_compact(&SECP256K1, &data[64..128], recid)?;
-    let recovered = SECP256K1.recover(&message, &sig)?;
-    let key = recovered.serialize_vec(&SECP256K1, false);
-
-    let ret_generic = Keccak256::digest(&key[1..65]);
-    let mut ret = [0u8; 32];
-
-    for i in 0..32 {
-        ret[i] = ret_generic[i];
-    }
-
-    Ok(ret)
-}
-
-#[cfg(feature = "rust-secp256k1")]
-fn kececrec(data: &[u8]) -> Result<[u8; 32], Error> {
-    let mut message_raw = [0u8; 32];
-    message_raw[..32].clone_from_slice(&data[..32]);
-    let message = Message::parse(&message_raw);
-    let recid_raw = match data[63] {
-        27 | 28 if data[32..63] == [0; 31] => data[63] - 27,
-        _ => return Err(Error::InvalidRecoveryId),
-    };
-    let recid = RecoveryId::parse(recid_raw)?;
-    let mut sig_raw = [0u8; 64];
-    sig_raw[..64].clone_from_slice(&data[64..128]);
-    let sig = Signature::parse(&sig_raw);
-    let recovered = recover(&message, &sig, &recid)?;
-    let key = recovered.serialize();
-
-    let ret_generic = Keccak256::digest(&key[1..65]);
-    let mut ret = [0u8; 32];
-
-    ret[..32].clone_from_slice(&ret_generic[..32]);
-
-    Ok(ret)
-}
@@ -0,0 +1,157 @@
+//! Precompiled contract sets built at runtime.
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap as Map;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use bigint::{Address, U256};
+use smallvec::SmallVec;
+
+use crate::patch::Precompiled;
+
+#[derive(Clone)]
+/// Precompiled contract registered in a `PrecompiledRegistry`.
+pub struct PrecompiledEntry {
+    /// Code required at the address for the precompiled contract to
+    /// run, if any.
+    pub code: Option<Arc<Vec<u8>>>,
+    /// Definition of the precompiled contract.
+    pub precompiled: Arc<dyn Precompiled + Send>,
+    /// Block number from which the precompiled contract is enabled.
+    pub activation_block: U256,
+}
+
+#[derive(Clone, Default)]
+/// Owned set of precompiled contracts, keyed by address, that can be
+/// built at runtime, for example from a chainspec file.
+pub struct PrecompiledRegistry {
+    entries: Map<Address, PrecompiledEntry>,
+}
+
+impl PrecompiledRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry from a static precompiled contract set, with
+    /// every contract enabled from the given block.
+    pub fn from_static(
+        precompileds: &'static [(Address, Option<&'static [u8]>, &'static dyn Precompiled)],
+        activation_block: U256,
+    ) -> Self {
+        let mut registry = Self::new();
+        for &(address, code, precompiled) in precompileds {
+            registry.insert(
+                address,
+                code.map(|code| Arc::new(code.into())),
+                Arc::new(precompiled),
+                activation_block,
+            );
+        }
+        registry
+    }
+
+    /// Register a precompiled contract at the given address, enabled
+    /// from the given block. Returns the entry it replaced, if any.
+    pub fn insert(
+        &mut self,
+        address: Address,
+        code: Option<Arc<Vec<u8>>>,
+        precompiled: Arc<dyn Precompiled + Send>,
+        activation_block: U256,
+    ) -> Option<PrecompiledEntry> {
+        self.entries.insert(
+            address,
+            PrecompiledEntry {
+                code,
+                precompiled,
+                activation_block,
+            },
+        )
+    }
+
+    /// Remove the precompiled contract at the given address.
+    pub fn remove(&mut self, address: &Address) -> Option<PrecompiledEntry> {
+        self.entries.remove(address)
+    }
+
+    /// Precompiled contract registered at the given address.
+    pub fn get(&self, address: &Address) -> Option<&PrecompiledEntry> {
+        self.entries.get(address)
+    }
+
+    /// Precompiled contract at the given address, if it is enabled in
+    /// the block of the given number and the code at the address
+    /// matches the required one.
+    pub fn find(&self, address: &Address, code: &[u8], number: U256) -> Option<&dyn Precompiled> {
+        self.entries
+            .get(address)
+            .filter(|entry| entry.activation_block <= number)
+            .and_then(|entry| match entry.code {
+                Some(ref required) if required.as_slice() != code => None,
+                _ => Some(&*entry.precompiled as &dyn Precompiled),
+            })
+    }
+
+    /// Addresses of all registered precompiled contracts.
+    pub fn addresses(&self) -> impl Iterator<Item = &Address> {
+        self.entries.keys()
+    }
+
+    /// Addresses of the precompiled contracts enabled at the given
+    /// block, suitable for `DynamicPatch::enabled_precompileds`.
+    pub fn enabled_at(&self, number: U256) -> SmallVec<[Address; 8]> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.activation_block <= number)
+            .map(|(address, _)| *address)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patch::{ID_PRECOMPILED, SHA256_PRECOMPILED};
+
+    #[test]
+    fn registry_entries() {
+        let id = Address::from(0x4);
+        let custom = Address::from(0x100);
+
+        let mut registry = PrecompiledRegistry::new();
+        registry.insert(id, None, Arc::new(&ID_PRECOMPILED), U256::zero());
+        registry.insert(
+            custom,
+            Some(Arc::new(vec![0x01])),
+            Arc::new(&SHA256_PRECOMPILED),
+            U256::from(100),
+        );
+
+        assert_eq!(registry.enabled_at(U256::from(99)).as_slice(), &[id]);
+        assert_eq!(registry.enabled_at(U256::from(100)).as_slice(), &[id, custom]);
+        assert!(registry.find(&id, &[], U256::zero()).is_some());
+        assert!(registry.find(&custom, &[], U256::from(100)).is_none());
+        assert!(registry.find(&custom, &[0x01], U256::from(99)).is_none());
+        assert!(registry.find(&custom, &[0x01], U256::from(100)).is_some());
+
+        assert!(registry.remove(&id).is_some());
+        assert!(registry.find(&id, &[], U256::zero()).is_none());
+        assert_eq!(registry.addresses().count(), 1);
+    }
+
+    #[test]
+    fn dynamic_patch_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PrecompiledRegistry>();
+        assert_send_sync::<crate::patch::DynamicPatch>();
+    }
+}
@@ -11,6 +11,7 @@ use std::cmp::min;
 
 use super::errors::OnChainError;
 use super::Patch;
+use crate::eof::{is_eof, EofContainer, FunctionType};
 use crate::util::opcode::Opcode;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -65,6 +66,11 @@ pub enum Instruction {
     NUMBER,
     DIFFICULTY,
     GASLIMIT,
+    CHAINID,
+    SELFBALANCE,
+    BASEFEE,
+    BLOBHASH,
+    BLOBBASEFEE,
     POP,
     MLOAD,
     MSTORE,
@@ -77,6 +83,9 @@ pub enum Instruction {
     MSIZE,
     GAS,
     JUMPDEST,
+    TLOAD,
+    TSTORE,
+    MCOPY,
     CREATE,
     CREATE2,
     CALL,
@@ -89,14 +98,35 @@ pub enum Instruction {
     RETURNDATASIZE,
     RETURNDATACOPY,
 
+    PUSH0,
     PUSH(M256),
     DUP(usize),
     SWAP(usize),
     LOG(usize),
+
+    RJUMP(i16),
+    RJUMPI(i16),
+    RJUMPV(u8),
+    CALLF(usize),
+    RETF,
 }
 
-/// Mapping of valid jump destination from code.
-pub struct Valids(Vec<bool>);
+/// Code sections of an EOF container, by their offsets in the
+/// container and their types. (EIP3540, EIP4750)
+enum EofCode {
+    Valid {
+        offsets: Vec<usize>,
+        types: Vec<FunctionType>,
+    },
+    Invalid,
+}
+
+/// Mapping of valid jump destination from code. For EOF code, which
+/// has no jump destinations, it holds the code sections instead.
+pub struct Valids {
+    jumpdests: Vec<bool>,
+    eof: Option<EofCode>,
+}
 
 impl Valids {
     /// Create a new valid mapping from given code bytes.
@@ -121,14 +151,47 @@ impl Valids {
             }
         }
 
-        Valids(valids)
+        Valids {
+            jumpdests: valids,
+            eof: None,
+        }
+    }
+
+    /// Create the mapping of an EOF container from given code bytes.
+    /// An invalid container fails on its first instruction. (EIP3540)
+    pub fn new_eof(code: &[u8]) -> Self {
+        let eof = match EofContainer::parse(code).and_then(|container| {
+            container.validate()?;
+            Ok(container)
+        }) {
+            Ok(container) => EofCode::Valid {
+                offsets: container.code_offsets(),
+                types: container.types,
+            },
+            Err(_) => EofCode::Invalid,
+        };
+
+        Valids {
+            jumpdests: Vec::new(),
+            eof: Some(eof),
+        }
+    }
+
+    /// Create the mapping of code as executed under the patch, which
+    /// reads EOF containers if the patch has EOF.
+    pub fn with_patch<P: Patch>(patch: &P, code: &[u8]) -> Self {
+        if patch.has_eof() && is_eof(code) {
+            Self::new_eof(code)
+        } else {
+            Self::new(code)
+        }
     }
 
     /// Get the length of the valid mapping. This is the same as the
     /// code bytes.
     #[inline]
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.jumpdests.len()
     }
 
     /// Returns true if the valids list is empty
@@ -140,16 +203,36 @@ impl Valids {
     /// Returns `true` if the position is a valid jump destination. If
     /// not, returns `false`.
     pub fn is_valid(&self, position: usize) -> bool {
-        if position >= self.0.len() {
+        if position >= self.jumpdests.len() {
             return false;
         }
 
-        if !self.0[position] {
+        if !self.jumpdests[position] {
             return false;
         }
 
         true
     }
+
+    /// Returns `true` if the code is executed as an EOF container.
+    pub fn is_eof(&self) -> bool {
+        self.eof.is_some()
+    }
+
+    /// Position execution starts from, which is the first code section
+    /// of an EOF container.
+    pub fn entry(&self) -> usize {
+        self.code_section(0).map(|(offset, _)| offset).unwrap_or(0)
+    }
+
+    /// Offset in the container and type of an EOF code section, or
+    /// `None` if it does not exist. (EIP4750)
+    pub fn code_section(&self, index: usize) -> Option<(usize, FunctionType)> {
+        match self.eof {
+            Some(EofCode::Valid { ref offsets, ref types }) => offsets.get(index).map(|offset| (*offset, types[index])),
+            _ => None,
+        }
+    }
 }
 
 /// Represents a program counter in EVM.
@@ -180,6 +263,13 @@ macro_rules! impl_pc {
                 Ok(M256::from(&self.code[position..max]))
             }
 
+            fn read_u16(&self, position: usize) -> Result<u16, OnChainError> {
+                match self.code.get(position..position.saturating_add(2)) {
+                    Some(bytes) => Ok(u16::from(bytes[0]) << 8 | u16::from(bytes[1])),
+                    None => Err(OnChainError::PCOverflow),
+                }
+            }
+
             /// Get the code bytearray.
             pub fn code(&self) -> &[u8] {
                 &self.code
@@ -231,6 +321,9 @@ macro_rules! impl_pc {
 
             /// Peek the next instruction.
             pub fn peek(&self) -> Result<Instruction, OnChainError> {
+                if let Some(EofCode::Invalid) = self.valids.eof {
+                    return Err(OnChainError::InvalidCode);
+                }
                 let opcode: Opcode = self.peek_opcode()?;
                 Ok(match opcode {
                     Opcode::STOP => Instruction::STOP,
@@ -303,6 +396,41 @@ macro_rules! impl_pc {
                     Opcode::NUMBER => Instruction::NUMBER,
                     Opcode::DIFFICULTY => Instruction::DIFFICULTY,
                     Opcode::GASLIMIT => Instruction::GASLIMIT,
+                    Opcode::CHAINID => {
+                        if self.patch.has_chain_id() {
+                            Instruction::CHAINID
+                        } else {
+                            return Err(OnChainError::InvalidOpcode);
+                        }
+                    }
+                    Opcode::SELFBALANCE => {
+                        if self.patch.has_self_balance() {
+                            Instruction::SELFBALANCE
+                        } else {
+                            return Err(OnChainError::InvalidOpcode);
+                        }
+                    }
+                    Opcode::BASEFEE => {
+                        if self.patch.has_base_fee() {
+                            Instruction::BASEFEE
+                        } else {
+                            return Err(OnChainError::InvalidOpcode);
+                        }
+                    }
+                    Opcode::BLOBHASH => {
+                        if self.patch.has_blobs() {
+                            Instruction::BLOBHASH
+                        } else {
+                            return Err(OnChainError::InvalidOpcode);
+                        }
+                    }
+                    Opcode::BLOBBASEFEE => {
+                        if self.patch.has_blobs() {
+                            Instruction::BLOBBASEFEE
+                        } else {
+                            return Err(OnChainError::InvalidOpcode);
+                        }
+                    }
 
                     Opcode::POP => Instruction::POP,
                     Opcode::MLOAD => Instruction::MLOAD,
@@ -316,7 +444,35 @@ macro_rules! impl_pc {
                     Opcode::MSIZE => Instruction::MSIZE,
                     Opcode::GAS => Instruction::GAS,
                     Opcode::JUMPDEST => Instruction::JUMPDEST,
+                    Opcode::TLOAD => {
+                        if self.patch.has_transient_storage() {
+                            Instruction::TLOAD
+                        } else {
+                            return Err(OnChainError::InvalidOpcode);
+                        }
+                    }
+                    Opcode::TSTORE => {
+                        if self.patch.has_transient_storage() {
+                            Instruction::TSTORE
+                        } else {
+                            return Err(OnChainError::InvalidOpcode);
+                        }
+                    }
+                    Opcode::MCOPY => {
+                        if self.patch.has_mcopy() {
+                            Instruction::MCOPY
+                        } else {
+                            return Err(OnChainError::InvalidOpcode);
+                        }
+                    }
 
+                    Opcode::PUSH0 => {
+                        if self.patch.has_push0() {
+                            Instruction::PUSH0
+                        } else {
+                            return Err(OnChainError::InvalidOpcode);
+                        }
+                    }
                     Opcode::PUSH(v) => {
                         let param = self.read_bytes(*self.position + 1, v)?;
                         Instruction::PUSH(param)
@@ -326,6 +482,20 @@ macro_rules! impl_pc {
                     Opcode::SWAP(v) => Instruction::SWAP(v),
                     Opcode::LOG(v) => Instruction::LOG(v),
 
+                    Opcode::RJUMP | Opcode::RJUMPI | Opcode::RJUMPV | Opcode::CALLF | Opcode::RETF
+                        if !self.valids.is_eof() =>
+                    {
+                        return Err(OnChainError::InvalidOpcode);
+                    }
+                    Opcode::RJUMP => Instruction::RJUMP(self.read_u16(*self.position + 1)? as i16),
+                    Opcode::RJUMPI => Instruction::RJUMPI(self.read_u16(*self.position + 1)? as i16),
+                    Opcode::RJUMPV => match self.code.get(*self.position + 1) {
+                        Some(max_index) => Instruction::RJUMPV(*max_index),
+                        None => return Err(OnChainError::PCOverflow),
+                    },
+                    Opcode::CALLF => Instruction::CALLF(self.read_u16(*self.position + 1)? as usize),
+                    Opcode::RETF => Instruction::RETF,
+
                     Opcode::CREATE => Instruction::CREATE,
                     Opcode::CREATE2 => {
                         if self.patch.has_create2() {
@@ -424,14 +594,31 @@ impl<'a, P: Patch> PCMut<'a, P> {
         Ok(())
     }
 
+    /// Move to a position in EOF code, which container validation
+    /// guarantees to start an instruction. (EIP4200, EIP4750)
+    pub fn goto(&mut self, position: usize) -> Result<(), OnChainError> {
+        if position >= self.code.len() {
+            return Err(OnChainError::PCOverflow);
+        }
+
+        *self.position = position;
+        Ok(())
+    }
+
     /// Read the next instruction and step the program counter.
     pub fn read(&mut self) -> Result<Instruction, OnChainError> {
         let result = self.peek()?;
         let opcode: Opcode = self.code[*self.position].into();
-        match opcode {
-            Opcode::PUSH(v) => {
+        match (opcode, result) {
+            (Opcode::PUSH(v), _) => {
                 *self.position = min(*self.position + v + 1, self.code.len());
             }
+            (_, Instruction::RJUMP(_)) | (_, Instruction::RJUMPI(_)) | (_, Instruction::CALLF(_)) => {
+                *self.position += 3;
+            }
+            (_, Instruction::RJUMPV(max_index)) => {
+                *self.position += 2 + (max_index as usize + 1) * 2;
+            }
             _ => {
                 *self.position += 1;
             }
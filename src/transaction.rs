@@ -1,7 +1,7 @@
 //! Transaction related functionality.
 
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 
 #[cfg(not(feature = "std"))]
 use alloc::rc::Rc;
@@ -10,7 +10,7 @@ use std::rc::Rc;
 
 #[cfg(not(feature = "std"))]
 use alloc::{collections::btree_map as map, collections::BTreeSet as Set};
-use bigint::{Address, Gas, H256, U256};
+use bigint::{Address, Gas, H256, M256, U256};
 #[cfg(not(feature = "std"))]
 use core::cmp::min;
 #[cfg(not(feature = "std"))]
@@ -22,10 +22,12 @@ use std::collections::{hash_map as map, HashSet as Set};
 #[cfg(feature = "std")]
 use std::ops::Deref;
 
-use super::errors::{CommitError, PreExecutionError, RequireError};
+use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
+
+use super::errors::{CommitError, NotSupportedError, PreExecutionError, RequireError};
 use super::{
-    AccountChange, AccountCommitment, AccountState, BlockhashState, Context, ContextVM, HeaderParams, Instruction, Log,
-    Machine, Memory, Opcode, Patch, State, VMStatus, VM,
+    AccountChange, AccountCommitment, AccountState, BlockhashState, Context, ContextVM, CryptoProvider, HeaderParams,
+    Instruction, Log, Machine, Memory, Opcode, Patch, State, VMStatus, VM,
 };
 
 #[cfg(feature = "std")]
@@ -35,6 +37,14 @@ use block_core::TransactionAction;
 const G_TXDATAZERO: usize = 4;
 const G_TXDATANONZERO: usize = 68;
 const G_TRANSACTION: usize = 21000;
+const G_ACCESSLISTADDRESS: usize = 2400;
+const G_ACCESSLISTSTORAGE: usize = 1900;
+const G_PERBLOB: usize = 131_072;
+/// Version byte of blob versioned hashes derived from KZG commitments.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+/// Type byte of the envelope of an access list transaction. (EIP2718,
+/// EIP2930)
+pub const ACCESS_LIST_TRANSACTION_TYPE: u8 = 0x01;
 
 static SYSTEM_ADDRESS: [u8; 20] = [0xff; 20];
 
@@ -44,6 +54,147 @@ macro_rules! system_address {
     };
 }
 
+/// An address and its storage keys declared in a transaction access
+/// list. (EIP2930)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessListItem {
+    /// Accessed address.
+    pub address: Address,
+    /// Accessed storage keys of the address.
+    pub storage_keys: Vec<U256>,
+}
+
+impl Encodable for AccessListItem {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+        s.append(&self.address);
+        s.begin_list(self.storage_keys.len());
+        for key in &self.storage_keys {
+            s.append(&H256::from(key));
+        }
+    }
+}
+
+impl Decodable for AccessListItem {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 2 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        let storage_keys: Vec<H256> = rlp.list_at(1)?;
+        Ok(AccessListItem {
+            address: rlp.val_at(0)?,
+            storage_keys: storage_keys.into_iter().map(U256::from).collect(),
+        })
+    }
+}
+
+/// A signed access list transaction, as carried by a typed
+/// transaction envelope of type 0x01. (EIP2718, EIP2930)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessListTransaction {
+    /// Chain ID the transaction is signed for.
+    pub chain_id: U256,
+    /// Nonce of the transaction.
+    pub nonce: U256,
+    /// Gas price of the transaction.
+    pub gas_price: Gas,
+    /// Gas limit of the transaction.
+    pub gas_limit: Gas,
+    /// Action CALL/CREATE of the transaction.
+    pub action: TransactionAction,
+    /// Value sent with this transaction.
+    pub value: U256,
+    /// Transaction input.
+    pub input: Vec<u8>,
+    /// Addresses and storage keys accessed by the transaction.
+    pub access_list: Vec<AccessListItem>,
+    /// Parity of the y value of the signature, 0 or 1.
+    pub y_parity: u8,
+    /// `r` value of the signature.
+    pub r: H256,
+    /// `s` value of the signature.
+    pub s: H256,
+}
+
+impl AccessListTransaction {
+    /// Decode the transaction from its envelope, the type byte
+    /// followed by the RLP of the transaction.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecoderError> {
+        match bytes.first() {
+            Some(&ACCESS_LIST_TRANSACTION_TYPE) => (),
+            Some(_) => return Err(DecoderError::Custom("unknown transaction type")),
+            None => return Err(DecoderError::RlpIsTooShort),
+        }
+
+        let rlp = UntrustedRlp::new(&bytes[1..]);
+        if rlp.item_count()? != 11 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        let y_parity: u8 = rlp.val_at(8)?;
+        if y_parity > 1 {
+            return Err(DecoderError::Custom("invalid signature y parity"));
+        }
+
+        Ok(AccessListTransaction {
+            chain_id: rlp.val_at(0)?,
+            nonce: rlp.val_at(1)?,
+            gas_price: rlp.val_at(2)?,
+            gas_limit: rlp.val_at(3)?,
+            action: rlp.val_at(4)?,
+            value: rlp.val_at(5)?,
+            input: rlp.val_at(6)?,
+            access_list: rlp.list_at(7)?,
+            y_parity,
+            r: rlp.val_at(9)?,
+            s: rlp.val_at(10)?,
+        })
+    }
+
+    /// Encode the transaction into its envelope.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut s = RlpStream::new_list(11);
+        self.rlp_append_unsigned(&mut s);
+        s.append(&self.y_parity);
+        s.append(&self.r);
+        s.append(&self.s);
+
+        let mut bytes = vec![ACCESS_LIST_TRANSACTION_TYPE];
+        bytes.extend_from_slice(&s.out());
+        bytes
+    }
+
+    fn rlp_append_unsigned(&self, s: &mut RlpStream) {
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.gas_price);
+        s.append(&self.gas_limit);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.input);
+        s.append_list(&self.access_list);
+    }
+
+    /// Hash signed by the sender, covering the type byte and every
+    /// field but the signature.
+    pub fn signing_hash(&self, crypto: &dyn CryptoProvider) -> H256 {
+        let mut s = RlpStream::new_list(8);
+        self.rlp_append_unsigned(&mut s);
+
+        let mut payload = vec![ACCESS_LIST_TRANSACTION_TYPE];
+        payload.extend_from_slice(&s.out());
+        crypto.keccak256(&payload)
+    }
+
+    /// Recover the sender of the transaction. Returns `None` if the
+    /// signature is invalid.
+    pub fn caller(&self, crypto: &dyn CryptoProvider) -> Result<Option<Address>, NotSupportedError> {
+        let mut signature = [0u8; 64];
+        signature[0..32].copy_from_slice(&self.r);
+        signature[32..64].copy_from_slice(&self.s);
+        crypto.ecrecover(&self.signing_hash(crypto), self.y_parity, &signature)
+    }
+}
+
 /// Represents an Ethereum transaction.
 ///
 /// ## About SYSTEM transaction
@@ -62,8 +213,13 @@ macro_rules! system_address {
 pub struct UntrustedTransaction {
     /// The caller. Must be attached with its commitment,
     pub caller: AccountCommitment,
-    /// Transaction gas price.
+    /// Transaction gas price. Ignored for EIP1559 transactions.
     pub gas_price: Gas,
+    /// Maximum fee per gas of an EIP1559 transaction. None for
+    /// legacy transactions.
+    pub max_fee_per_gas: Option<Gas>,
+    /// Maximum priority fee per gas of an EIP1559 transaction.
+    pub max_priority_fee_per_gas: Option<Gas>,
     /// Transaction gas limit.
     pub gas_limit: Gas,
     /// Action CALL/CREATE of the transaction.
@@ -72,9 +228,34 @@ pub struct UntrustedTransaction {
     pub value: U256,
     /// Transaction input.
     pub input: Rc<Vec<u8>>,
+    /// Access list of the transaction. Empty for legacy transactions.
+    pub access_list: Vec<AccessListItem>,
+    /// Maximum fee per blob gas of a blob transaction. None for other
+    /// transactions. (EIP4844)
+    pub max_fee_per_blob_gas: Option<Gas>,
+    /// Versioned hashes of the blobs carried by the transaction.
+    pub blob_versioned_hashes: Rc<Vec<H256>>,
 }
 
 impl UntrustedTransaction {
+    /// Whether the blob fields are consistent. A blob transaction must
+    /// be a message call carrying at least one blob, and every
+    /// versioned hash must use the KZG version. (EIP4844)
+    fn has_valid_blobs<P: Patch>(&self, patch: &P) -> bool {
+        match self.max_fee_per_blob_gas {
+            Some(_) => {
+                patch.has_blobs()
+                    && !self.blob_versioned_hashes.is_empty()
+                    && self.action != TransactionAction::Create
+                    && self
+                        .blob_versioned_hashes
+                        .iter()
+                        .all(|hash| hash[0] == VERSIONED_HASH_VERSION_KZG)
+            }
+            None => self.blob_versioned_hashes.is_empty(),
+        }
+    }
+
     /// Convert to a valid transaction.
     pub fn to_valid<P: Patch>(&self, patch: &P) -> Result<ValidTransaction, PreExecutionError> {
         let valid = {
@@ -88,13 +269,25 @@ impl UntrustedTransaction {
                 _ => return Err(PreExecutionError::InvalidCaller),
             };
 
+            if !self.has_valid_blobs(patch) {
+                return Err(PreExecutionError::InvalidBlobs);
+            }
+
+            if !self.access_list.is_empty() && !patch.has_warm_cold_access() {
+                return Err(PreExecutionError::UnsupportedTransactionType);
+            }
+
             let gas_limit: U256 = self.gas_limit.into();
-            let gas_price: U256 = self.gas_price.into();
+            let gas_price: U256 = self.max_fee_per_gas.unwrap_or(self.gas_price).into();
+            let blob_gas: U256 = (Gas::from(G_PERBLOB) * Gas::from(self.blob_versioned_hashes.len())).into();
+            let blob_gas_price: U256 = self.max_fee_per_blob_gas.unwrap_or(Gas::zero()).into();
 
             let (preclaimed_value, overflowed1) = gas_limit.overflowing_mul(gas_price);
-            let (total, overflowed2) = preclaimed_value.overflowing_add(self.value);
+            let (blob_value, overflowed2) = blob_gas.overflowing_mul(blob_gas_price);
+            let (total, overflowed3) = preclaimed_value.overflowing_add(self.value);
+            let (total, overflowed4) = total.overflowing_add(blob_value);
 
-            if overflowed1 || overflowed2 {
+            if overflowed1 || overflowed2 || overflowed3 || overflowed4 {
                 return Err(PreExecutionError::InsufficientBalance);
             }
 
@@ -105,15 +298,22 @@ impl UntrustedTransaction {
             ValidTransaction {
                 caller: Some(address),
                 gas_price: self.gas_price,
+                max_fee_per_gas: self.max_fee_per_gas,
+                max_priority_fee_per_gas: self.max_priority_fee_per_gas,
                 gas_limit: self.gas_limit,
                 action: self.action,
                 value: self.value,
                 input: self.input.clone(),
                 nonce,
+                access_list: self.access_list.clone(),
+                max_fee_per_blob_gas: self.max_fee_per_blob_gas,
+                blob_versioned_hashes: self.blob_versioned_hashes.clone(),
             }
         };
 
-        if valid.gas_limit < valid.intrinsic_gas(patch.gas_transaction_create()) {
+        if valid.exceeds_initcode_limit(patch) {
+            Err(PreExecutionError::InitcodeSizeExceeded)
+        } else if valid.gas_limit < valid.intrinsic_gas_with_patch(patch) {
             Err(PreExecutionError::InsufficientGasLimit)
         } else {
             Ok(valid)
@@ -140,8 +340,14 @@ pub struct ValidTransaction {
     /// Caller of this transaction. If caller is None, then this is a
     /// SYSTEM transaction.
     pub caller: Option<Address>,
-    /// Gas price of this transaction.
+    /// Gas price of this transaction. Ignored for EIP1559
+    /// transactions, which pay the effective gas price instead.
     pub gas_price: Gas,
+    /// Maximum fee per gas of an EIP1559 transaction. None for
+    /// legacy transactions.
+    pub max_fee_per_gas: Option<Gas>,
+    /// Maximum priority fee per gas of an EIP1559 transaction.
+    pub max_priority_fee_per_gas: Option<Gas>,
     /// Gas limit of this transaction.
     pub gas_limit: Gas,
     /// Transaction action.
@@ -152,38 +358,149 @@ pub struct ValidTransaction {
     pub input: Rc<Vec<u8>>,
     /// Nonce of the transaction.
     pub nonce: U256,
+    /// Access list of the transaction. Empty for legacy transactions.
+    pub access_list: Vec<AccessListItem>,
+    /// Maximum fee per blob gas of a blob transaction. None for other
+    /// transactions. (EIP4844)
+    pub max_fee_per_blob_gas: Option<Gas>,
+    /// Versioned hashes of the blobs carried by the transaction.
+    pub blob_versioned_hashes: Rc<Vec<H256>>,
 }
 
 #[cfg(feature = "std")]
 impl ValidTransaction {
-    /// Create a valid transaction from a block transaction. Caller is
-    /// always Some.
+    /// Create a valid transaction from a block transaction,
+    /// recovering its caller with the patch's crypto provider. Caller
+    /// is always Some.
     pub fn from_transaction<P: Patch>(
         patch: &P,
         transaction: &Transaction,
         account_state: &AccountState<P::Account>,
     ) -> Result<Result<ValidTransaction, PreExecutionError>, RequireError> {
-        let caller = match transaction.caller() {
-            Ok(val) => val,
-            Err(_) => return Ok(Err(PreExecutionError::InvalidCaller)),
+        let caller = match Self::recover_caller(transaction, patch.crypto()) {
+            Ok(Some(val)) => val,
+            _ => return Ok(Err(PreExecutionError::InvalidCaller)),
         };
 
+        Self::check_with_state(
+            patch,
+            ValidTransaction {
+                caller: Some(caller),
+                gas_price: transaction.gas_price,
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                gas_limit: transaction.gas_limit,
+                action: transaction.action,
+                value: transaction.value,
+                input: Rc::new(transaction.input.clone()),
+                nonce: transaction.nonce,
+                access_list: Vec::new(),
+                max_fee_per_blob_gas: None,
+                blob_versioned_hashes: Rc::new(Vec::new()),
+            },
+            account_state,
+        )
+    }
+
+    /// Hash signed by the sender of a block transaction. Covers the
+    /// chain ID if the signature is replay protected. (EIP155)
+    pub fn signing_hash(transaction: &Transaction, crypto: &dyn CryptoProvider) -> H256 {
+        let chain_id = transaction.signature.chain_id();
+
+        let mut s = RlpStream::new_list(if chain_id.is_some() { 9 } else { 6 });
+        s.append(&transaction.nonce);
+        s.append(&transaction.gas_price);
+        s.append(&transaction.gas_limit);
+        s.append(&transaction.action);
+        s.append(&transaction.value);
+        s.append(&transaction.input);
+        if let Some(chain_id) = chain_id {
+            s.append(&chain_id);
+            s.append(&0u8);
+            s.append(&0u8);
+        }
+
+        crypto.keccak256(&s.out())
+    }
+
+    /// Recover the sender of a block transaction. Returns `None` if
+    /// the signature is invalid.
+    pub fn recover_caller(
+        transaction: &Transaction,
+        crypto: &dyn CryptoProvider,
+    ) -> Result<Option<Address>, NotSupportedError> {
+        let recovery_id = transaction.signature.standard_v();
+        if recovery_id > 1 {
+            return Ok(None);
+        }
+
+        let mut signature = [0u8; 64];
+        signature[0..32].copy_from_slice(&transaction.signature.r);
+        signature[32..64].copy_from_slice(&transaction.signature.s);
+        crypto.ecrecover(&Self::signing_hash(transaction, crypto), recovery_id, &signature)
+    }
+}
+
+impl ValidTransaction {
+    /// Create a valid transaction from an access list transaction,
+    /// recovering its caller with the patch's crypto provider. Caller
+    /// is always Some. (EIP2930)
+    pub fn from_access_list_transaction<P: Patch>(
+        patch: &P,
+        transaction: &AccessListTransaction,
+        account_state: &AccountState<P::Account>,
+    ) -> Result<Result<ValidTransaction, PreExecutionError>, RequireError> {
+        if !patch.has_warm_cold_access() {
+            return Ok(Err(PreExecutionError::UnsupportedTransactionType));
+        }
+        if transaction.chain_id != patch.chain_id() {
+            return Ok(Err(PreExecutionError::InvalidChainId));
+        }
+
+        let caller = match transaction.caller(patch.crypto()) {
+            Ok(Some(val)) => val,
+            _ => return Ok(Err(PreExecutionError::InvalidCaller)),
+        };
+
+        Self::check_with_state(
+            patch,
+            ValidTransaction {
+                caller: Some(caller),
+                gas_price: transaction.gas_price,
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                gas_limit: transaction.gas_limit,
+                action: transaction.action,
+                value: transaction.value,
+                input: Rc::new(transaction.input.clone()),
+                nonce: transaction.nonce,
+                access_list: transaction.access_list.clone(),
+                max_fee_per_blob_gas: None,
+                blob_versioned_hashes: Rc::new(Vec::new()),
+            },
+            account_state,
+        )
+    }
+
+    /// Check a transaction of a recovered caller against the nonce
+    /// and balance of the caller.
+    fn check_with_state<P: Patch>(
+        patch: &P,
+        valid: ValidTransaction,
+        account_state: &AccountState<P::Account>,
+    ) -> Result<Result<ValidTransaction, PreExecutionError>, RequireError> {
+        let caller = valid.caller.unwrap();
+
         let nonce = account_state.nonce(caller)?;
-        if nonce != transaction.nonce {
+        if nonce != valid.nonce {
             return Ok(Err(PreExecutionError::InvalidNonce));
         }
 
-        let valid = ValidTransaction {
-            caller: Some(caller),
-            gas_price: transaction.gas_price,
-            gas_limit: transaction.gas_limit,
-            action: transaction.action,
-            value: transaction.value,
-            input: Rc::new(transaction.input.clone()),
-            nonce,
-        };
+        if valid.exceeds_initcode_limit(patch) {
+            return Ok(Err(PreExecutionError::InitcodeSizeExceeded));
+        }
 
-        if valid.gas_limit < valid.intrinsic_gas(patch.gas_transaction_create()) {
+        if valid.gas_limit < valid.intrinsic_gas_with_patch(patch) {
             return Ok(Err(PreExecutionError::InsufficientGasLimit));
         }
 
@@ -208,13 +525,47 @@ impl ValidTransaction {
 
 impl ValidTransaction {
     /// To address of the transaction.
+    #[deprecated(note = "use `address_with_crypto`, which uses the patch crypto provider")]
     pub fn address(&self) -> Address {
         self.action
             .address(self.caller.unwrap_or(system_address!()), self.nonce)
     }
 
+    /// To address of the transaction. Contract addresses are derived
+    /// with the given crypto provider.
+    pub fn address_with_crypto(&self, crypto: &dyn CryptoProvider) -> Address {
+        let caller = self.caller.unwrap_or(system_address!());
+        let hash = match self.action {
+            TransactionAction::Call(address) => return address,
+            TransactionAction::Create => {
+                let mut s = RlpStream::new_list(2);
+                s.append(&caller);
+                s.append(&self.nonce);
+                crypto.keccak256(&s.out())
+            }
+            TransactionAction::Create2(salt, code_hash) => {
+                let mut data = vec![0xff];
+                data.extend_from_slice(&caller);
+                data.extend_from_slice(&salt);
+                data.extend_from_slice(&H256::from(code_hash));
+                crypto.keccak256(&data)
+            }
+        };
+        Address::from(M256::from(hash))
+    }
+
+    /// Whether this is a contract creation whose init code exceeds
+    /// the patch's init code size limit.
+    pub fn exceeds_initcode_limit<P: Patch>(&self, patch: &P) -> bool {
+        match (&self.action, patch.initcode_limit()) {
+            (&TransactionAction::Create, Some(limit)) => self.input.len() > limit,
+            _ => false,
+        }
+    }
+
     /// Intrinsic gas to be paid in prior to this transaction
-    /// execution.
+    /// execution, without the init code and access list costs.
+    #[deprecated(note = "use `intrinsic_gas_with_patch`, which also charges init code words and access lists")]
     pub fn intrinsic_gas(&self, gas_transaction_create: Gas) -> Gas {
         let mut gas = Gas::from(G_TRANSACTION);
 
@@ -233,8 +584,45 @@ impl ValidTransaction {
         gas
     }
 
+    /// Intrinsic gas to be paid in prior to this transaction
+    /// execution.
+    pub fn intrinsic_gas_with_patch<P: Patch>(&self, patch: &P) -> Gas {
+        let mut gas = Gas::from(G_TRANSACTION);
+
+        if self.action == TransactionAction::Create {
+            gas = gas + patch.gas_transaction_create();
+
+            // Init code words are only charged when the init code
+            // size is limited, as for CREATE and CREATE2. (EIP3860)
+            if patch.initcode_limit().is_some() {
+                let len = self.input.len();
+                let words = len / 32 + if len % 32 == 0 { 0 } else { 1 };
+                gas = gas + patch.gas_initcode_word() * Gas::from(words);
+            }
+        }
+
+        for d in self.input.deref() {
+            if *d == 0 {
+                gas = gas + Gas::from(G_TXDATAZERO);
+            } else {
+                gas = gas + Gas::from(G_TXDATANONZERO);
+            }
+        }
+
+        if patch.has_warm_cold_access() {
+            for item in &self.access_list {
+                gas = gas + Gas::from(G_ACCESSLISTADDRESS);
+                gas = gas + Gas::from(G_ACCESSLISTSTORAGE) * Gas::from(item.storage_keys.len());
+            }
+        }
+
+        gas
+    }
+
     /// Convert this transaction into a context. Note that this will
     /// change the account state.
+    #[deprecated(note = "use `into_context_with_patch`, which uses the patch crypto provider")]
+    #[allow(deprecated)]
     pub fn into_context<P: Patch>(
         self,
         upfront: Gas,
@@ -244,7 +632,34 @@ impl ValidTransaction {
         is_static: bool,
     ) -> Result<Context, RequireError> {
         let address = self.address();
+        self.into_context_at::<P>(address, upfront, origin, account_state, is_code, is_static)
+    }
+
+    /// Convert this transaction into a context, deriving contract
+    /// addresses with the crypto provider of the patch. Note that
+    /// this will change the account state.
+    pub fn into_context_with_patch<P: Patch>(
+        self,
+        patch: &P,
+        upfront: Gas,
+        origin: Option<Address>,
+        account_state: &mut AccountState<P::Account>,
+        is_code: bool,
+        is_static: bool,
+    ) -> Result<Context, RequireError> {
+        let address = self.address_with_crypto(patch.crypto());
+        self.into_context_at::<P>(address, upfront, origin, account_state, is_code, is_static)
+    }
 
+    fn into_context_at<P: Patch>(
+        self,
+        address: Address,
+        upfront: Gas,
+        origin: Option<Address>,
+        account_state: &mut AccountState<P::Account>,
+        is_code: bool,
+        is_static: bool,
+    ) -> Result<Context, RequireError> {
         // Calculate gas with overflow checks
         let gas_limit: U256 = self.gas_limit.into();
         let upfront: U256 = upfront.into();
@@ -284,6 +699,7 @@ impl ValidTransaction {
                     apprent_value: self.value,
                     is_system: self.caller.is_none(),
                     is_static,
+                    blob_versioned_hashes: self.blob_versioned_hashes,
                 })
             }
             TransactionAction::Create | TransactionAction::Create2(..) => {
@@ -308,11 +724,59 @@ impl ValidTransaction {
                     apprent_value: self.value,
                     is_system: self.caller.is_none(),
                     is_static,
+                    blob_versioned_hashes: self.blob_versioned_hashes,
                 })
             }
         }
     }
 
+    /// Gas price actually paid by this transaction given the block
+    /// base fee. For EIP1559 transactions this is the base fee plus the
+    /// priority fee, capped at the max fee. (EIP1559)
+    pub fn effective_gas_price(&self, base_fee: Gas) -> Gas {
+        match self.max_fee_per_gas {
+            Some(max_fee) => {
+                let priority_fee = self.max_priority_fee_per_gas.unwrap_or(Gas::zero());
+                min(max_fee, base_fee + priority_fee)
+            }
+            None => self.gas_price,
+        }
+    }
+
+    /// Check the fee caps of this transaction against the patch and
+    /// the block base fees. Fee market transactions are rejected by
+    /// patches without a base fee. (EIP1559, EIP4844)
+    pub fn check_fee_cap<P: Patch>(&self, patch: &P, block: &HeaderParams) -> Result<(), PreExecutionError> {
+        if self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some() {
+            if !patch.has_base_fee() {
+                return Err(PreExecutionError::UnsupportedTransactionType);
+            }
+            let max_fee = self.max_fee_per_gas.ok_or(PreExecutionError::InvalidFeeCap)?;
+            if self
+                .max_priority_fee_per_gas
+                .is_some_and(|priority_fee| priority_fee > max_fee)
+            {
+                return Err(PreExecutionError::InvalidFeeCap);
+            }
+        }
+        if patch.has_base_fee() && self.max_fee_per_gas.unwrap_or(self.gas_price) < block.base_fee {
+            return Err(PreExecutionError::InvalidFeeCap);
+        }
+        if patch.has_blobs()
+            && self
+                .max_fee_per_blob_gas
+                .is_some_and(|max_fee| max_fee < block.blob_base_fee(patch))
+        {
+            return Err(PreExecutionError::InvalidFeeCap);
+        }
+        Ok(())
+    }
+
+    /// Blob gas used by the blobs of this transaction. (EIP4844)
+    pub fn blob_gas(&self) -> Gas {
+        Gas::from(G_PERBLOB) * Gas::from(self.blob_versioned_hashes.len())
+    }
+
     /// When the execution of a transaction begins, this preclaimed
     /// value is deducted from the account.
     pub fn preclaimed_value(&self) -> U256 {
@@ -326,6 +790,7 @@ enum TransactionVMState<'a, M, P: Patch> {
         vm: ContextVM<'a, M, P>,
         intrinsic_gas: Gas,
         preclaimed_value: U256,
+        blob_fee: U256,
         finalized: bool,
         code_deposit: bool,
         fresh_account_state: AccountState<'a, P::Account>,
@@ -352,6 +817,7 @@ impl<'a, M: Memory, P: Patch> TransactionVM<'a, M, P> {
         block: HeaderParams,
     ) -> Result<Self, PreExecutionError> {
         let valid = transaction.to_valid(patch)?;
+        valid.check_fee_cap(patch, &block)?;
         let account_patch = patch.account_patch();
         let mut vm = TransactionVM(TransactionVMState::Constructing {
             patch,
@@ -365,16 +831,19 @@ impl<'a, M: Memory, P: Patch> TransactionVM<'a, M, P> {
     }
 
     /// Create a new VM using the given transaction, block header and
-    /// patch. This VM runs at the transaction level.
-    pub fn new(patch: &'a P, transaction: ValidTransaction, block: HeaderParams) -> Self {
+    /// patch. This VM runs at the transaction level. Returns an error
+    /// if the fee caps of the transaction do not cover the block base
+    /// fees.
+    pub fn new(patch: &'a P, transaction: ValidTransaction, block: HeaderParams) -> Result<Self, PreExecutionError> {
+        transaction.check_fee_cap(patch, &block)?;
         let account_patch = patch.account_patch();
-        TransactionVM(TransactionVMState::Constructing {
+        Ok(TransactionVM(TransactionVMState::Constructing {
             patch,
             transaction,
             block,
             account_state: AccountState::new(account_patch),
             blockhash_state: BlockhashState::default(),
-        })
+        }))
     }
 
     /// Returns the current state of the VM.
@@ -497,6 +966,8 @@ impl<'a, M: Memory, P: Patch> VM for TransactionVM<'a, M, P> {
         let cblockhash_state: BlockhashState;
         let ccode_deposit: bool;
         let cpreclaimed_value: U256;
+        let cblob_fee: U256;
+        let caccess_list: Vec<AccessListItem>;
 
         let real_used_gas = self.used_gas();
 
@@ -507,6 +978,7 @@ impl<'a, M: Memory, P: Patch> VM for TransactionVM<'a, M, P> {
                 ref mut code_deposit,
                 ref fresh_account_state,
                 preclaimed_value,
+                blob_fee,
                 ..
             } => match vm.status() {
                 VMStatus::Running => {
@@ -525,8 +997,10 @@ impl<'a, M: Memory, P: Patch> VM for TransactionVM<'a, M, P> {
                     if !*finalized {
                         vm.machines[0].finalize_transaction(
                             vm.runtime.block.beneficiary,
+                            vm.runtime.block.base_fee,
                             real_used_gas,
                             preclaimed_value,
+                            blob_fee,
                             fresh_account_state,
                         )?;
                         *finalized = true;
@@ -543,19 +1017,36 @@ impl<'a, M: Memory, P: Patch> VM for TransactionVM<'a, M, P> {
                 ref mut account_state,
                 ref blockhash_state,
             } => {
-                let address = transaction.address();
+                let address = transaction.address_with_crypto(patch.crypto());
                 account_state.require(address)?;
 
+                let base_fee = if patch.has_base_fee() {
+                    block.base_fee
+                } else {
+                    Gas::zero()
+                };
+                let mut transaction = transaction.clone();
+                transaction.gas_price = transaction.effective_gas_price(base_fee);
+
                 ccode_deposit = match transaction.action {
                     TransactionAction::Create | TransactionAction::Create2(..) => true,
                     TransactionAction::Call(_) => false,
                 };
                 cpatch = patch;
-                cgas = transaction.intrinsic_gas(cpatch.gas_transaction_create());
-                cpreclaimed_value = transaction.preclaimed_value();
-                ccontext = transaction
-                    .clone()
-                    .into_context::<P>(cgas, None, account_state, false, false)?;
+                cgas = transaction.intrinsic_gas_with_patch(cpatch);
+                // The blob fee is paid upfront along with the
+                // preclaimed gas, and is never refunded.
+                cblob_fee = if patch.has_blobs() {
+                    (transaction.blob_gas() * block.blob_base_fee(patch)).into()
+                } else {
+                    U256::zero()
+                };
+                cpreclaimed_value = transaction.preclaimed_value() + cblob_fee;
+                caccess_list = transaction.access_list.clone();
+                ccontext =
+                    transaction
+                        .clone()
+                        .into_context_with_patch(cpatch, cgas, None, account_state, false, false)?;
                 cblock = block.clone();
                 caccount_state = account_state.clone();
                 cblockhash_state = blockhash_state.clone();
@@ -570,6 +1061,7 @@ impl<'a, M: Memory, P: Patch> VM for TransactionVM<'a, M, P> {
             account_state.clone(),
             cblockhash_state,
             |vm| {
+                vm.machines[0].initialize_access_list(&caccess_list);
                 if ccode_deposit {
                     vm.machines[0].initialize_create(cpreclaimed_value).unwrap();
                 } else {
@@ -586,6 +1078,7 @@ impl<'a, M: Memory, P: Patch> VM for TransactionVM<'a, M, P> {
             finalized: false,
             code_deposit: ccode_deposit,
             preclaimed_value: cpreclaimed_value,
+            blob_fee: cblob_fee,
         };
 
         Ok(())
@@ -643,10 +1136,13 @@ impl<'a, M: Memory, P: Patch> VM for TransactionVM<'a, M, P> {
     fn used_gas(&self) -> Gas {
         match self.0 {
             TransactionVMState::Running {
-                ref vm, intrinsic_gas, ..
+                patch,
+                ref vm,
+                intrinsic_gas,
+                ..
             } => {
                 let total_used = vm.machines[0].state().total_used_gas() + intrinsic_gas;
-                let refund_cap = total_used / Gas::from(2u64);
+                let refund_cap = total_used / Gas::from(patch.max_refund_quotient().get());
                 let refunded = min(refund_cap, vm.machines[0].state().refunded_gas);
                 total_used - refunded
             }
@@ -657,11 +1153,72 @@ impl<'a, M: Memory, P: Patch> VM for TransactionVM<'a, M, P> {
 
 #[cfg(test)]
 mod tests {
+    use crate::eval::tests::test_patch;
     use crate::*;
     use bigint::*;
-    use block::TransactionAction;
+    use block::{Transaction, TransactionAction, TransactionSignature};
+    use hexutil::read_hex;
     use std::rc::Rc;
     use std::str::FromStr;
+    use std::sync::Arc;
+
+    /// Test patch with access lists, the EIP2929 access costs, the
+    /// EIP1559 base fee and the given crypto provider.
+    fn access_list_patch<C: CryptoProvider + Send + Sync + 'static>(crypto: C) -> DynamicPatch {
+        DynamicPatch {
+            has_warm_cold_access: true,
+            has_base_fee: true,
+            crypto: Arc::new(crypto),
+            ..test_patch()
+        }
+    }
+
+    /// Provider recovering a fixed address, after checking the hash
+    /// and the recovery ID it is given.
+    struct FixedRecoveryProvider {
+        hash: H256,
+        recovery_id: u8,
+        address: Address,
+    }
+
+    impl CryptoProvider for FixedRecoveryProvider {
+        fn keccak256(&self, data: &[u8]) -> H256 {
+            NATIVE_CRYPTO_PROVIDER.keccak256(data)
+        }
+        fn sha256(&self, data: &[u8]) -> H256 {
+            NATIVE_CRYPTO_PROVIDER.sha256(data)
+        }
+        fn ripemd160(&self, data: &[u8]) -> [u8; 20] {
+            NATIVE_CRYPTO_PROVIDER.ripemd160(data)
+        }
+        fn ecrecover(&self, hash: &H256, recovery_id: u8, _: &[u8; 64]) -> Result<Option<Address>, NotSupportedError> {
+            assert_eq!(*hash, self.hash);
+            assert_eq!(recovery_id, self.recovery_id);
+            Ok(Some(self.address))
+        }
+    }
+
+    /// Provider hashing everything to the same Keccak-256 hash.
+    struct FixedHashProvider;
+
+    impl CryptoProvider for FixedHashProvider {
+        fn keccak256(&self, _: &[u8]) -> H256 {
+            H256::from(0x1234)
+        }
+        fn sha256(&self, data: &[u8]) -> H256 {
+            NATIVE_CRYPTO_PROVIDER.sha256(data)
+        }
+        fn ripemd160(&self, data: &[u8]) -> [u8; 20] {
+            NATIVE_CRYPTO_PROVIDER.ripemd160(data)
+        }
+        fn ecrecover(&self, _: &H256, _: u8, _: &[u8; 64]) -> Result<Option<Address>, NotSupportedError> {
+            Ok(None)
+        }
+    }
+
+    /// Signed access list transaction from the go-ethereum EIP2718
+    /// test cases.
+    const ACCESS_LIST_TRANSACTION: &str = "0x01f8630103018261a894b94f5374fce5edbc8e2a8697c15331677e6ebf0b0a825544c001a0c9519f4f2b30335884581971573fadf60c6204f59a911df35ee8a540456b2660a032f1e8e2c5dd761f9e4f88f41c8310aeaba26a8bfcdacfedfa12ec3862d37521";
 
     #[test]
     fn system_transaction() {
@@ -669,11 +1226,16 @@ mod tests {
         let transaction = ValidTransaction {
             caller: None,
             gas_price: Gas::zero(),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
             gas_limit: Gas::from_str("0xffffffffffffffff").unwrap(),
             action: TransactionAction::Call(Address::default()),
             value: U256::from_str("0xffffffffffffffff").unwrap(),
             input: Rc::new(Vec::new()),
             nonce: U256::zero(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Rc::new(Vec::new()),
         };
         let mut vm = SeqTransactionVM::new(
             &patch,
@@ -683,9 +1245,13 @@ mod tests {
                 timestamp: 0,
                 number: U256::zero(),
                 difficulty: U256::zero(),
+                prev_randao: H256::default(),
                 gas_limit: Gas::zero(),
+                base_fee: Gas::zero(),
+                excess_blob_gas: Gas::zero(),
             },
-        );
+        )
+        .unwrap();
         vm.commit_account(AccountCommitment::Nonexist(Address::default()))
             .unwrap();
         vm.fire().unwrap();
@@ -709,11 +1275,16 @@ mod tests {
         let transaction = ValidTransaction {
             caller: None,
             gas_price: Gas::one(),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
             gas_limit: Gas::from_str("0xffffffffffffffff").unwrap(),
             action: TransactionAction::Call(Address::default()),
             value: U256::from_str("0xffffffffffffffff").unwrap(),
             input: Rc::new(Vec::new()),
             nonce: U256::zero(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Rc::new(Vec::new()),
         };
         let patch = EmbeddedPatch::default();
         let mut vm = SeqTransactionVM::new(
@@ -724,9 +1295,13 @@ mod tests {
                 timestamp: 0,
                 number: U256::zero(),
                 difficulty: U256::zero(),
+                prev_randao: H256::default(),
                 gas_limit: Gas::zero(),
+                base_fee: Gas::zero(),
+                excess_blob_gas: Gas::zero(),
             },
-        );
+        )
+        .unwrap();
         vm.commit_account(AccountCommitment::Nonexist(Address::default()))
             .unwrap();
         vm.fire().unwrap();
@@ -744,6 +1319,317 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn intrinsic_gas_with_access_list() {
+        let transaction = ValidTransaction {
+            caller: Some(Address::default()),
+            gas_price: Gas::zero(),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            gas_limit: Gas::from(100000u64),
+            action: TransactionAction::Call(Address::default()),
+            value: U256::zero(),
+            input: Rc::new(Vec::new()),
+            nonce: U256::zero(),
+            access_list: vec![AccessListItem {
+                address: Address::default(),
+                storage_keys: vec![U256::zero(), U256::one()],
+            }],
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Rc::new(Vec::new()),
+        };
+
+        assert_eq!(
+            transaction.intrinsic_gas_with_patch(&access_list_patch(NativeCryptoProvider)),
+            Gas::from(21000u64 + 2400 + 2 * 1900)
+        );
+        // Without EIP2929 access lists are not charged for
+        assert_eq!(
+            transaction.intrinsic_gas_with_patch(&EmbeddedPatch::default()),
+            Gas::from(21000u64)
+        );
+    }
+
+    #[test]
+    fn access_list_transaction_envelope() {
+        let bytes = read_hex(ACCESS_LIST_TRANSACTION).unwrap();
+        let transaction = AccessListTransaction::decode(&bytes).unwrap();
+        assert_eq!(transaction.chain_id, U256::one());
+        assert_eq!(transaction.nonce, U256::from(3u64));
+        assert_eq!(transaction.gas_price, Gas::one());
+        assert_eq!(transaction.gas_limit, Gas::from(25000u64));
+        assert_eq!(
+            transaction.action,
+            TransactionAction::Call(Address::from_str("b94f5374fce5edbc8e2a8697c15331677e6ebf0b").unwrap())
+        );
+        assert_eq!(transaction.value, U256::from(10u64));
+        assert_eq!(transaction.input, vec![0x55, 0x44]);
+        assert_eq!(transaction.y_parity, 1);
+        assert_eq!(transaction.encode(), bytes);
+        assert_eq!(
+            transaction.signing_hash(&NATIVE_CRYPTO_PROVIDER),
+            H256::from_str("49b486f0ec0a60dfbbca2d30cb07c9e8ffb2a2ff41f29a1ab6737475f6ff69f3").unwrap()
+        );
+
+        let mut with_access_list = transaction.clone();
+        with_access_list.access_list = vec![AccessListItem {
+            address: Address::default(),
+            storage_keys: vec![U256::zero(), U256::max_value()],
+        }];
+        assert_eq!(
+            AccessListTransaction::decode(&with_access_list.encode()).unwrap(),
+            with_access_list
+        );
+
+        // Legacy transactions are not typed
+        assert!(AccessListTransaction::decode(&bytes[1..]).is_err());
+        let mut invalid_parity = bytes.clone();
+        invalid_parity[35] = 0x02;
+        assert!(AccessListTransaction::decode(&invalid_parity).is_err());
+    }
+
+    #[test]
+    fn valid_access_list_transaction() {
+        let mut transaction = AccessListTransaction::decode(&read_hex(ACCESS_LIST_TRANSACTION).unwrap()).unwrap();
+        transaction.access_list = vec![AccessListItem {
+            address: Address::default(),
+            storage_keys: vec![U256::zero()],
+        }];
+        transaction.gas_limit = Gas::from(30000u64);
+        let caller = Address::from(0x1234);
+
+        let crypto = FixedRecoveryProvider {
+            hash: transaction.signing_hash(&NATIVE_CRYPTO_PROVIDER),
+            recovery_id: 1,
+            address: caller,
+        };
+        let patch = access_list_patch(crypto);
+        let mut account_state = AccountState::new(patch.account_patch());
+        account_state
+            .commit(AccountCommitment::Full {
+                nonce: U256::from(3u64),
+                address: caller,
+                balance: U256::from(100000u64),
+                code: Rc::new(Vec::new()),
+            })
+            .unwrap();
+
+        let valid = ValidTransaction::from_access_list_transaction(&patch, &transaction, &account_state)
+            .unwrap()
+            .unwrap();
+        assert_eq!(valid.caller, Some(caller));
+        assert_eq!(valid.access_list, transaction.access_list);
+        assert_eq!(
+            valid.intrinsic_gas_with_patch(&patch),
+            Gas::from(21000u64 + 2 * 68 + 2400 + 1900)
+        );
+
+        match ValidTransaction::from_access_list_transaction(&test_patch(), &transaction, &account_state) {
+            Ok(Err(PreExecutionError::UnsupportedTransactionType)) => (),
+            _ => panic!(),
+        }
+        transaction.chain_id = U256::from(2u64);
+        match ValidTransaction::from_access_list_transaction(&patch, &transaction, &account_state) {
+            Ok(Err(PreExecutionError::InvalidChainId)) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn valid_legacy_transaction() {
+        // Replay protected transaction from the EIP155 example
+        let mut transaction = Transaction {
+            nonce: U256::from(9u64),
+            gas_price: Gas::from(20_000_000_000u64),
+            gas_limit: Gas::from(21000u64),
+            action: TransactionAction::Call(Address::from_str("3535353535353535353535353535353535353535").unwrap()),
+            value: U256::from(1_000_000_000_000_000_000u64),
+            signature: TransactionSignature {
+                v: 37,
+                r: H256::from_str("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276").unwrap(),
+                s: H256::from_str("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83").unwrap(),
+            },
+            input: Vec::new(),
+        };
+        assert_eq!(
+            ValidTransaction::signing_hash(&transaction, &NATIVE_CRYPTO_PROVIDER),
+            H256::from_str("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53").unwrap()
+        );
+        let caller = Address::from(0x1234);
+
+        let crypto = FixedRecoveryProvider {
+            hash: ValidTransaction::signing_hash(&transaction, &NATIVE_CRYPTO_PROVIDER),
+            recovery_id: 0,
+            address: caller,
+        };
+        let patch = access_list_patch(crypto);
+        let mut account_state = AccountState::new(patch.account_patch());
+        account_state
+            .commit(AccountCommitment::Full {
+                nonce: U256::from(9u64),
+                address: caller,
+                balance: U256::from(2_000_000_000_000_000_000u64),
+                code: Rc::new(Vec::new()),
+            })
+            .unwrap();
+
+        let valid = ValidTransaction::from_transaction(&patch, &transaction, &account_state)
+            .unwrap()
+            .unwrap();
+        assert_eq!(valid.caller, Some(caller));
+
+        transaction.signature.v = 29;
+        match ValidTransaction::from_transaction(&patch, &transaction, &account_state) {
+            Ok(Err(PreExecutionError::InvalidCaller)) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn contract_address() {
+        let mut transaction = ValidTransaction {
+            caller: Some(Address::from_str("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0").unwrap()),
+            gas_price: Gas::zero(),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            gas_limit: Gas::from(100000u64),
+            action: TransactionAction::Create,
+            value: U256::zero(),
+            input: Rc::new(Vec::new()),
+            nonce: U256::zero(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Rc::new(Vec::new()),
+        };
+        assert_eq!(
+            transaction.address_with_crypto(&NATIVE_CRYPTO_PROVIDER),
+            Address::from_str("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d").unwrap()
+        );
+        // The derivation goes through the given provider
+        assert_eq!(
+            transaction.address_with_crypto(&FixedHashProvider),
+            Address::from(0x1234)
+        );
+
+        // First example of EIP1014
+        transaction.caller = Some(Address::default());
+        transaction.action =
+            TransactionAction::Create2(H256::default(), M256::from(NATIVE_CRYPTO_PROVIDER.keccak256(&[0x00])));
+        assert_eq!(
+            transaction.address_with_crypto(&NATIVE_CRYPTO_PROVIDER),
+            Address::from_str("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38").unwrap()
+        );
+    }
+
+    #[test]
+    fn blob_gas() {
+        let transaction = ValidTransaction {
+            caller: Some(Address::default()),
+            gas_price: Gas::zero(),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            gas_limit: Gas::from(100000u64),
+            action: TransactionAction::Call(Address::default()),
+            value: U256::zero(),
+            input: Rc::new(Vec::new()),
+            nonce: U256::zero(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: Some(Gas::one()),
+            blob_versioned_hashes: Rc::new(vec![H256::default(), H256::default()]),
+        };
+
+        assert_eq!(transaction.blob_gas(), Gas::from(2 * 131072u64));
+        assert_eq!(
+            transaction.intrinsic_gas_with_patch(&EmbeddedPatch::default()),
+            Gas::from(21000u64)
+        );
+    }
+
+    #[test]
+    fn effective_gas_price() {
+        let mut transaction = ValidTransaction {
+            caller: Some(Address::default()),
+            gas_price: Gas::from(3u64),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            gas_limit: Gas::from(100000u64),
+            action: TransactionAction::Call(Address::default()),
+            value: U256::zero(),
+            input: Rc::new(Vec::new()),
+            nonce: U256::zero(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Rc::new(Vec::new()),
+        };
+        assert_eq!(transaction.effective_gas_price(Gas::from(5u64)), Gas::from(3u64));
+
+        transaction.max_fee_per_gas = Some(Gas::from(10u64));
+        transaction.max_priority_fee_per_gas = Some(Gas::from(2u64));
+        assert_eq!(transaction.effective_gas_price(Gas::from(5u64)), Gas::from(7u64));
+        assert_eq!(transaction.effective_gas_price(Gas::from(9u64)), Gas::from(10u64));
+    }
+
+    #[test]
+    fn fee_cap() {
+        let mut transaction = ValidTransaction {
+            caller: Some(Address::default()),
+            gas_price: Gas::from(3u64),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            gas_limit: Gas::from(100000u64),
+            action: TransactionAction::Call(Address::default()),
+            value: U256::zero(),
+            input: Rc::new(Vec::new()),
+            nonce: U256::zero(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Rc::new(Vec::new()),
+        };
+        let mut block = HeaderParams {
+            beneficiary: Address::default(),
+            timestamp: 0,
+            number: U256::zero(),
+            difficulty: U256::zero(),
+            prev_randao: H256::default(),
+            gas_limit: Gas::zero(),
+            base_fee: Gas::from(5u64),
+            excess_blob_gas: Gas::zero(),
+        };
+        let patch = access_list_patch(NativeCryptoProvider);
+
+        assert!(transaction.check_fee_cap(&EmbeddedPatch::default(), &block).is_ok());
+        match transaction.check_fee_cap(&patch, &block) {
+            Err(PreExecutionError::InvalidFeeCap) => (),
+            _ => panic!(),
+        }
+
+        transaction.max_fee_per_gas = Some(Gas::from(10u64));
+        transaction.max_priority_fee_per_gas = Some(Gas::from(2u64));
+        assert!(transaction.check_fee_cap(&patch, &block).is_ok());
+        match transaction.check_fee_cap(&EmbeddedPatch::default(), &block) {
+            Err(PreExecutionError::UnsupportedTransactionType) => (),
+            _ => panic!(),
+        }
+
+        block.base_fee = Gas::from(11u64);
+        match transaction.check_fee_cap(&patch, &block) {
+            Err(PreExecutionError::InvalidFeeCap) => (),
+            _ => panic!(),
+        }
+
+        block.base_fee = Gas::from(5u64);
+        transaction.max_priority_fee_per_gas = Some(Gas::from(11u64));
+        match transaction.check_fee_cap(&patch, &block) {
+            Err(PreExecutionError::InvalidFeeCap) => (),
+            _ => panic!(),
+        }
+        // The VM rejects the transaction instead of panicking
+        match SeqTransactionVM::new(&patch, transaction, block) {
+            Err(PreExecutionError::InvalidFeeCap) => (),
+            _ => panic!(),
+        }
+    }
     /*
         #[test]
         fn eip140_spec_test() {
@@ -759,6 +1645,7 @@ mod tests {
                 apprent_value: U256::zero(),
                 is_system: false,
                 is_static: false,
+                blob_versioned_hashes: Rc::new(Vec::new()),
             };
 
             let header = HeaderParams {
@@ -766,6 +1653,7 @@ mod tests {
                 timestamp: 0,
                 number: U256::zero(),
                 difficulty: U256::zero(),
+                prev_randao: H256::default(),
                 gas_limit: Gas::from(100000usize),
             };
 
@@ -58,6 +58,11 @@ pub enum Opcode {
     NUMBER,
     DIFFICULTY,
     GASLIMIT,
+    CHAINID,
+    SELFBALANCE,
+    BASEFEE,
+    BLOBHASH,
+    BLOBBASEFEE,
 
     POP,
     MLOAD,
@@ -71,12 +76,22 @@ pub enum Opcode {
     MSIZE,
     GAS,
     JUMPDEST,
+    TLOAD,
+    TSTORE,
+    MCOPY,
 
+    PUSH0,
     PUSH(usize),
     DUP(usize),
     SWAP(usize),
     LOG(usize),
 
+    RJUMP,
+    RJUMPI,
+    RJUMPV,
+    CALLF,
+    RETF,
+
     CREATE,
     CREATE2,
     CALL,
@@ -147,6 +162,11 @@ impl From<u8> for Opcode {
             0x43 => Opcode::NUMBER,
             0x44 => Opcode::DIFFICULTY,
             0x45 => Opcode::GASLIMIT,
+            0x46 => Opcode::CHAINID,
+            0x47 => Opcode::SELFBALANCE,
+            0x48 => Opcode::BASEFEE,
+            0x49 => Opcode::BLOBHASH,
+            0x4a => Opcode::BLOBBASEFEE,
 
             0x50 => Opcode::POP,
             0x51 => Opcode::MLOAD,
@@ -160,7 +180,11 @@ impl From<u8> for Opcode {
             0x59 => Opcode::MSIZE,
             0x5a => Opcode::GAS,
             0x5b => Opcode::JUMPDEST,
+            0x5c => Opcode::TLOAD,
+            0x5d => Opcode::TSTORE,
+            0x5e => Opcode::MCOPY,
 
+            0x5f => Opcode::PUSH0,
             0x60 => Opcode::PUSH(1),
             0x61 => Opcode::PUSH(2),
             0x62 => Opcode::PUSH(3),
@@ -234,6 +258,12 @@ impl From<u8> for Opcode {
             0xa3 => Opcode::LOG(3),
             0xa4 => Opcode::LOG(4),
 
+            0xe0 => Opcode::RJUMP,
+            0xe1 => Opcode::RJUMPI,
+            0xe2 => Opcode::RJUMPV,
+            0xe3 => Opcode::CALLF,
+            0xe4 => Opcode::RETF,
+
             0xf0 => Opcode::CREATE,
             0xf1 => Opcode::CALL,
             0xf2 => Opcode::CALLCODE,
@@ -306,6 +336,11 @@ impl Into<u8> for Opcode {
             Opcode::NUMBER => 0x43,
             Opcode::DIFFICULTY => 0x44,
             Opcode::GASLIMIT => 0x45,
+            Opcode::CHAINID => 0x46,
+            Opcode::SELFBALANCE => 0x47,
+            Opcode::BASEFEE => 0x48,
+            Opcode::BLOBHASH => 0x49,
+            Opcode::BLOBBASEFEE => 0x4a,
 
             Opcode::POP => 0x50,
             Opcode::MLOAD => 0x51,
@@ -319,7 +354,11 @@ impl Into<u8> for Opcode {
             Opcode::MSIZE => 0x59,
             Opcode::GAS => 0x5a,
             Opcode::JUMPDEST => 0x5b,
+            Opcode::TLOAD => 0x5c,
+            Opcode::TSTORE => 0x5d,
+            Opcode::MCOPY => 0x5e,
 
+            Opcode::PUSH0 => 0x5f,
             Opcode::PUSH(v) => {
                 assert!(v >= 1 && v <= 32);
                 0x5f + (v as u8)
@@ -340,6 +379,12 @@ impl Into<u8> for Opcode {
                 0xa0 + (v as u8)
             }
 
+            Opcode::RJUMP => 0xe0,
+            Opcode::RJUMPI => 0xe1,
+            Opcode::RJUMPV => 0xe2,
+            Opcode::CALLF => 0xe3,
+            Opcode::RETF => 0xe4,
+
             Opcode::CREATE => 0xf0,
             Opcode::CALL => 0xf1,
             Opcode::CALLCODE => 0xf2,
@@ -8,9 +8,9 @@ extern crate trie;
 #[macro_use]
 extern crate lazy_static;
 
-use bigint::{Address, Gas, U256};
+use bigint::{Address, Gas, H256, U256};
 use block::TransactionAction;
-use evm::{AccountChange, HeaderParams, SeqTransactionVM, Storage, ValidTransaction, VM};
+use evm::{AccessListItem, AccountChange, HeaderParams, SeqTransactionVM, Storage, ValidTransaction, VM};
 use evm_network_classic::MainnetEIP160Patch;
 use evm_stateful::{LiteralAccount, MemoryStateful};
 use hexutil::*;
@@ -145,11 +145,16 @@ impl Into<AccountChange> for SendableAccountChange {
 pub struct SendableValidTransaction {
     pub caller: Option<Address>,
     pub gas_price: Gas,
+    pub max_fee_per_gas: Option<Gas>,
+    pub max_priority_fee_per_gas: Option<Gas>,
     pub gas_limit: Gas,
     pub action: TransactionAction,
     pub value: U256,
     pub input: Vec<u8>,
     pub nonce: U256,
+    pub access_list: Vec<AccessListItem>,
+    pub max_fee_per_blob_gas: Option<Gas>,
+    pub blob_versioned_hashes: Vec<H256>,
 }
 
 impl From<ValidTransaction> for SendableValidTransaction {
@@ -158,19 +163,29 @@ impl From<ValidTransaction> for SendableValidTransaction {
             ValidTransaction {
                 caller,
                 gas_price,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
                 gas_limit,
                 action,
                 value,
                 input,
                 nonce,
+                access_list,
+                max_fee_per_blob_gas,
+                blob_versioned_hashes,
             } => SendableValidTransaction {
                 caller,
                 gas_price,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
                 gas_limit,
                 action,
                 value,
                 nonce,
                 input: input.deref().clone(),
+                access_list,
+                max_fee_per_blob_gas,
+                blob_versioned_hashes: blob_versioned_hashes.deref().clone(),
             },
         }
     }
@@ -182,19 +197,29 @@ impl Into<ValidTransaction> for SendableValidTransaction {
             SendableValidTransaction {
                 caller,
                 gas_price,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
                 gas_limit,
                 action,
                 value,
                 input,
                 nonce,
+                access_list,
+                max_fee_per_blob_gas,
+                blob_versioned_hashes,
             } => ValidTransaction {
                 caller,
                 gas_price,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
                 gas_limit,
                 action,
                 value,
                 nonce,
                 input: Rc::new(input),
+                access_list,
+                max_fee_per_blob_gas,
+                blob_versioned_hashes: Rc::new(blob_versioned_hashes),
             },
         }
     }
@@ -218,7 +243,10 @@ pub fn parallel_execute(
         timestamp: 0,
         number: U256::zero(),
         difficulty: U256::zero(),
+        prev_randao: H256::default(),
         gas_limit: Gas::max_value(),
+        base_fee: Gas::zero(),
+        excess_blob_gas: Gas::zero(),
     };
 
     let stateful = Arc::new(stateful);
@@ -232,7 +260,7 @@ pub fn parallel_execute(
 
         threads.push(thread::spawn(move || {
             let patch = MainnetEIP160Patch::default();
-            let vm: SeqTransactionVM<_> = stateful.call(&patch, transaction.into(), &header, &[]);
+            let vm: SeqTransactionVM<_> = stateful.call(&patch, transaction.into(), &header, &[]).unwrap();
             let accounts: Vec<SendableAccountChange> =
                 vm.accounts().map(|v| SendableAccountChange::from(v.clone())).collect();
             (accounts, vm.used_addresses())
@@ -259,7 +287,9 @@ pub fn parallel_execute(
             // Re-execute the transaction if conflict is detected.
             println!("Transaction index {}: conflict detected, re-execute.", index);
             let patch = MainnetEIP160Patch::default();
-            let vm: SeqTransactionVM<_> = stateful.call(&patch, transactions[index].clone(), &header, &[]);
+            let vm: SeqTransactionVM<_> = stateful
+                .call(&patch, transactions[index].clone(), &header, &[])
+                .unwrap();
             let accounts: Vec<AccountChange> = vm.accounts().map(|v| v.clone()).collect();
             (accounts, vm.used_addresses())
         } else {
@@ -316,28 +346,43 @@ fn main() {
                 caller: Some(addr1),
                 action: TransactionAction::Call(addr1),
                 gas_price: Gas::zero(),
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
                 gas_limit: Gas::max_value(),
                 value: U256::from_str("0x1000").unwrap(),
                 input: Rc::new(Vec::new()),
                 nonce: U256::zero(),
+                access_list: Vec::new(),
+                max_fee_per_blob_gas: None,
+                blob_versioned_hashes: Rc::new(Vec::new()),
             },
             ValidTransaction {
                 caller: Some(addr2),
                 action: TransactionAction::Call(addr3),
                 gas_price: Gas::zero(),
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
                 gas_limit: Gas::max_value(),
                 value: U256::from_str("0x1000").unwrap(),
                 input: Rc::new(Vec::new()),
                 nonce: U256::zero(),
+                access_list: Vec::new(),
+                max_fee_per_blob_gas: None,
+                blob_versioned_hashes: Rc::new(Vec::new()),
             },
             ValidTransaction {
                 caller: Some(addr3),
                 action: TransactionAction::Call(addr2),
                 gas_price: Gas::zero(),
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
                 gas_limit: Gas::max_value(),
                 value: U256::from_str("0x1000").unwrap(),
                 input: Rc::new(Vec::new()),
                 nonce: U256::zero(),
+                access_list: Vec::new(),
+                max_fee_per_blob_gas: None,
+                blob_versioned_hashes: Rc::new(Vec::new()),
             },
         ],
     );
@@ -210,10 +210,10 @@ impl<'b, D: DatabaseOwned> Stateful<'b, D> {
         transaction: ValidTransaction,
         block: &HeaderParams,
         most_recent_block_hashes: &[H256],
-    ) -> TransactionVM<'a, M, P> {
+    ) -> Result<TransactionVM<'a, M, P>, PreExecutionError> {
         assert!(U256::from(most_recent_block_hashes.len()) >= min(block.number, U256::from(256)));
 
-        let mut vm = TransactionVM::new(patch, transaction, block.clone());
+        let mut vm = TransactionVM::new(patch, transaction, block.clone())?;
         let state = self.database.create_fixed_secure_trie(self.root);
         let code_hashes = self.database.create_guard();
 
@@ -289,7 +289,7 @@ impl<'b, D: DatabaseOwned> Stateful<'b, D> {
             }
         }
 
-        vm
+        Ok(vm)
     }
 
     pub fn sets(&mut self, accounts: &[(Address, LiteralAccount)]) {
@@ -415,14 +415,14 @@ impl<'b, D: DatabaseOwned> Stateful<'b, D> {
         transaction: ValidTransaction,
         block: &HeaderParams,
         most_recent_block_hashes: &[H256],
-    ) -> TransactionVM<'a, M, P> {
-        let vm = self.call::<_, P>(patch, transaction, &block, most_recent_block_hashes);
+    ) -> Result<TransactionVM<'a, M, P>, PreExecutionError> {
+        let vm = self.call::<_, P>(patch, transaction, &block, most_recent_block_hashes)?;
         let mut accounts = Vec::new();
         for account in vm.accounts() {
             accounts.push(account.clone());
         }
         self.transit(&accounts);
-        vm
+        Ok(vm)
     }
 
     pub fn to_valid<P: Patch>(
@@ -108,26 +108,36 @@ fn genesis_state_root() {
         let address = Address::from_str(key).unwrap();
         let balance = U256::from_dec_str(&value.balance).unwrap();
         let patch = MainnetEIP160Patch::default();
-        let vm: SeqTransactionVM<_> = stateful.execute(
-            &patch,
-            ValidTransaction {
-                caller: None,
-                gas_price: Gas::zero(),
-                gas_limit: Gas::from(100000u64),
-                action: TransactionAction::Call(address),
-                value: balance,
-                input: empty_input.clone(),
-                nonce: U256::zero(),
-            },
-            &HeaderParams {
-                beneficiary: Address::default(),
-                timestamp: 0,
-                number: U256::zero(),
-                difficulty: U256::zero(),
-                gas_limit: Gas::max_value(),
-            },
-            &[],
-        );
+        let vm: SeqTransactionVM<_> = stateful
+            .execute(
+                &patch,
+                ValidTransaction {
+                    caller: None,
+                    gas_price: Gas::zero(),
+                    max_fee_per_gas: None,
+                    max_priority_fee_per_gas: None,
+                    gas_limit: Gas::from(100000u64),
+                    action: TransactionAction::Call(address),
+                    value: balance,
+                    input: empty_input.clone(),
+                    nonce: U256::zero(),
+                    access_list: Vec::new(),
+                    max_fee_per_blob_gas: None,
+                    blob_versioned_hashes: Rc::new(Vec::new()),
+                },
+                &HeaderParams {
+                    beneficiary: Address::default(),
+                    timestamp: 0,
+                    number: U256::zero(),
+                    difficulty: U256::zero(),
+                    prev_randao: H256::default(),
+                    gas_limit: Gas::max_value(),
+                    base_fee: Gas::zero(),
+                    excess_blob_gas: Gas::zero(),
+                },
+                &[],
+            )
+            .unwrap();
         match vm.status() {
             VMStatus::ExitedOk => (),
             _ => panic!(),